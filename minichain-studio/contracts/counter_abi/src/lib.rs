@@ -0,0 +1,127 @@
+use serde::{Deserialize, Serialize};
+
+/// The application call types that can be made to the counter application. Applications
+/// calling the counter build their calls with this type, so that they encode the same way.
+#[derive(Debug, Deserialize, Serialize)]
+pub enum CounterApplicationCall {
+    /// Increments the counter value by the step.
+    Increment,
+    /// Increments the counter value by the specified amount.
+    IncrementBy { value: u64 },
+    /// Decrements the counter value by the step.
+    Decrement,
+    /// Decrements the counter value by the specified amount.
+    DecrementBy { value: u64 },
+    /// Gets the current counter value.
+    GetValue,
+    /// Gets the total increments made on all chains, as far as this chain knows.
+    GetGlobalValue,
+    /// Opens a session that can increment the counter by up to `budget` in total.
+    OpenSession { budget: u64 },
+    /// Increments the counter named `key` by the step, and gets its new value.
+    IncrementKey { key: String },
+    /// Increments the counter named `key` by the specified amount, and gets its new value.
+    IncrementKeyBy { key: String, value: u64 },
+    /// Gets the value of the counter named `key`.
+    GetKey { key: String },
+    /// Gets the named counters whose key starts with `prefix`.
+    GetKeysWithPrefix { prefix: String },
+    /// Gets the `n` largest named counters.
+    GetTopKeys { n: usize },
+    /// Gets the latest changes of the counter value.
+    GetHistory,
+    /// Gets the changes aggregated per period of `resolution`, between `from` and `to` in
+    /// microseconds.
+    GetAggregates {
+        resolution: Resolution,
+        from: u64,
+        to: u64,
+    },
+}
+
+impl CounterApplicationCall {
+    /// Returns what the signer must be allowed to do to make the call.
+    pub fn permission(&self) -> Permission {
+        match self {
+            CounterApplicationCall::GetValue
+            | CounterApplicationCall::GetGlobalValue
+            | CounterApplicationCall::GetKey { .. }
+            | CounterApplicationCall::GetKeysWithPrefix { .. }
+            | CounterApplicationCall::GetTopKeys { .. }
+            | CounterApplicationCall::GetHistory
+            | CounterApplicationCall::GetAggregates { .. } => Permission::Read,
+            _ => Permission::Write,
+        }
+    }
+
+    /// Returns the amount the call changes counters by, counted against the caller's quota.
+    pub fn amount(&self, step: u64) -> u64 {
+        match self {
+            CounterApplicationCall::Increment
+            | CounterApplicationCall::Decrement
+            | CounterApplicationCall::IncrementKey { .. } => step,
+            CounterApplicationCall::IncrementBy { value }
+            | CounterApplicationCall::DecrementBy { value }
+            | CounterApplicationCall::IncrementKeyBy { value, .. } => *value,
+            CounterApplicationCall::OpenSession { budget } => *budget,
+            _ => 0,
+        }
+    }
+}
+
+/// What an operation, message or call needs the signer to be allowed to do. Each permission
+/// includes the ones before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Permission {
+    /// Reading the counter.
+    Read,
+    /// Changing the counter value.
+    Write,
+    /// Resetting and reconfiguring the counter, and managing roles.
+    Administer,
+}
+
+/// The length of the periods changes are aggregated over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Resolution {
+    /// Periods of one minute.
+    Minute,
+    /// Periods of one hour.
+    Hour,
+    /// Periods of one day.
+    Day,
+}
+
+impl Resolution {
+    /// Returns the length of a period, in microseconds.
+    pub const fn micros(self) -> u64 {
+        const MINUTE: u64 = 60_000_000;
+        match self {
+            Resolution::Minute => MINUTE,
+            Resolution::Hour => 60 * MINUTE,
+            Resolution::Day => 24 * 60 * MINUTE,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_call_permission_and_amount() {
+        let call = CounterApplicationCall::IncrementKey {
+            key: "poll-1".to_string(),
+        };
+        assert_eq!(call.permission(), Permission::Write);
+        assert_eq!(call.amount(3), 3);
+        let call = CounterApplicationCall::GetAggregates {
+            resolution: Resolution::Hour,
+            from: 0,
+            to: Resolution::Day.micros(),
+        };
+        assert_eq!(call.permission(), Permission::Read);
+        assert_eq!(call.amount(3), 0);
+        assert!(Permission::Read < Permission::Write);
+    }
+}
@@ -1,58 +1,850 @@
+pub use counter_abi::{CounterApplicationCall, Permission, Resolution};
+use idempotency::IdempotencyWindow;
+use linera_sdk::views::ViewStorageContext;
 use linera_sdk::{
-    base::{ApplicationId, ChainId, SessionId},
+    base::{ApplicationId, ChainId, Owner, SessionId, Timestamp},
     contract::system_api,
     ApplicationCallResult, CalleeContext, Contract, EffectContext, ExecutionResult,
     MessageContext, OperationContext, Resource, SessionCallResult, ViewStateStorage,
 };
+use linera_views::{
+    common::Context,
+    map_view::MapView,
+    views::{RootView, View, ViewError},
+};
 use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+
+mod service;
+
+/// The number of changes kept in the history of a counter.
+pub const HISTORY_LIMIT: usize = 1000;
+
+/// How long changes stay aggregated per minute before being rolled up into hours, in
+/// microseconds.
+pub const MINUTE_RETENTION: u64 = Resolution::Day.micros();
+
+/// How long changes stay aggregated per hour before being rolled up into days, in
+/// microseconds.
+pub const HOUR_RETENTION: u64 = 30 * Resolution::Day.micros();
 
 /// The state of the counter application.
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct Counter {
     /// The current value of the counter.
     pub value: u64,
+    /// The increments reserved by open sessions, not yet applied to `value`.
+    pub reserved: u64,
+    /// The range the value is kept in, and what happens to operations leaving it.
+    pub bounds: CounterBounds,
+    /// The parameters the application was published with, once this chain has read them.
+    pub parameters: Option<CounterParameters>,
+    /// The owner who instantiated the counter, and its first admin.
+    pub owner: Option<Owner>,
+    /// Who may change and read the counter.
+    pub access: AccessMode,
+    /// The role granted to each owner.
+    pub roles: BTreeMap<Owner, Role>,
+    /// The increments and decrements made on each chain, as far as this replica knows. Both
+    /// only ever grow, so replicas converge by taking the largest totals of each chain.
+    pub contributions: BTreeMap<ChainId, Contribution>,
+    /// The chains this chain publishes its contributions to.
+    pub subscribers: Vec<ChainId>,
+    /// The latest changes of the counter value, oldest first, at most [`HISTORY_LIMIT`].
+    pub history: VecDeque<HistoryEntry>,
+    /// The changes of the counter value aggregated per minute, by the start of the minute in
+    /// microseconds, for the last [`MINUTE_RETENTION`].
+    pub minutes: BTreeMap<u64, Bucket>,
+    /// The older changes aggregated per hour, by the start of the hour, for the last
+    /// [`HOUR_RETENTION`].
+    pub hours: BTreeMap<u64, Bucket>,
+    /// The older changes aggregated per day, by the start of the day. Unlike `history`, these
+    /// are never dropped.
+    pub days: BTreeMap<u64, Bucket>,
+    /// The permission and quota granted to each caller application.
+    pub caller_grants: BTreeMap<ApplicationId, CallerGrant>,
+    /// The calls made by each caller application.
+    pub caller_usage: BTreeMap<ApplicationId, CallerUsage>,
+    /// The latest idempotency keys of each signer, with the value their operation resulted in.
+    pub idempotency: IdempotencyWindow<Option<Owner>, u64>,
+}
+
+/// The argument a counter is instantiated with.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CounterArgument {
+    /// The value the counter starts at. Must be within `bounds`.
+    pub initial_value: u64,
+    /// The range the counter is kept in.
+    pub bounds: CounterBounds,
+    /// The owner of the counter, who becomes its first admin. Without one, the counter cannot
+    /// be reset, reconfigured or given roles.
+    pub owner: Option<Owner>,
+    /// Who may change and read the counter.
+    pub access: AccessMode,
+}
+
+/// The parameters fixed when the counter bytecode is published, shared by all its instances.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CounterParameters {
+    /// The amount `Increment` and `Decrement` change the counter by.
+    pub step: u64,
+}
+
+impl Default for CounterParameters {
+    fn default() -> Self {
+        CounterParameters { step: 1 }
+    }
+}
+
+/// Who may change and read a counter through operations, messages and application calls.
+/// The GraphQL service is not restricted: the state of a chain is readable by anyone who can
+/// query the chain.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AccessMode {
+    /// Anyone may change and read the counter.
+    #[default]
+    Open,
+    /// Only incrementers and admins may change the counter, but anyone may read it.
+    PublicRead,
+    /// Only incrementers and admins may change the counter or read it with application calls.
+    Private,
+}
+
+/// A role granted to an owner. Admins can do everything incrementers can.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Role {
+    /// May change and read the counter.
+    Incrementer,
+    /// May also reset and reconfigure the counter, and grant and revoke roles.
+    Admin,
+}
+
+/// What a caller application may do, regardless of the signer of the call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CallerGrant {
+    /// The most the application may do.
+    pub permission: Permission,
+    /// The total amount the application may change counters by, if limited.
+    pub quota: Option<u64>,
+}
+
+/// The calls made by a caller application.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CallerUsage {
+    /// The number of calls accepted.
+    pub calls: u64,
+    /// The total amount the application changed counters by, counted against its quota.
+    pub changed: u64,
+}
+
+/// The range a counter is kept in.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CounterBounds {
+    /// The smallest value of the counter. Defaults to 0.
+    pub min: Option<u64>,
+    /// The largest value of the counter. Defaults to `u64::MAX`.
+    pub max: Option<u64>,
+    /// What happens to operations that would leave the range.
+    pub overflow: Overflow,
+}
+
+/// What happens to operations that would take the counter out of its bounds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Overflow {
+    /// The operation fails with a [`CounterError`].
+    #[default]
+    Error,
+    /// The value stops at the bound.
+    Saturate,
+}
+
+/// The changes made to the counter on one chain.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Contribution {
+    /// The total of all increments.
+    pub increments: u64,
+    /// The total of all decrements.
+    pub decrements: u64,
+}
+
+/// A change of the counter value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// When the change was made.
+    pub timestamp: Timestamp,
+    /// How much the value changed, saturated to the range of `i64`.
+    pub delta: i64,
+    /// The signer who made the change, if any.
+    pub actor: Option<Owner>,
+}
+
+/// The changes of the counter value during a period of time.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Bucket {
+    /// The start of the period, in microseconds.
+    pub start: u64,
+    /// The number of changes.
+    pub changes: u64,
+    /// The sum of all increases.
+    pub increase: u64,
+    /// The sum of all decreases.
+    pub decrease: u64,
+    /// The value at the end of the period.
+    pub close: u64,
+}
+
+impl Bucket {
+    /// Adds the changes of `later`, a bucket of a later period, to this one.
+    fn merge(&mut self, later: &Bucket) {
+        self.changes += later.changes;
+        self.increase = self.increase.saturating_add(later.increase);
+        self.decrease = self.decrease.saturating_add(later.decrease);
+        self.close = later.close;
+    }
+}
+
+/// A session opened by a caller application, holding a reserved increment budget. Its
+/// increments are applied to the counter when the session is closed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CounterSession {
+    /// The largest total increment the session can apply.
+    pub budget: u64,
+    /// The increments applied within the session so far.
+    pub pending: u64,
+}
+
+/// The errors that can occur when updating the counter.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CounterError {
+    /// The counter value, including the increments reserved by sessions, would overflow.
+    Overflow,
+    /// The counter value would go below 0.
+    Underflow,
+    /// The counter value, including the increments reserved by sessions, would exceed its
+    /// maximum.
+    AboveMaximum { max: u64 },
+    /// The counter value would go below its minimum.
+    BelowMinimum { min: u64 },
+    /// The minimum of the bounds is larger than the maximum.
+    InvalidBounds,
+    /// The application parameters are malformed, or the step is 0.
+    InvalidParameters,
+    /// The signer is not allowed to do this.
+    Unauthorized,
+    /// The last admin of the counter cannot be revoked.
+    LastAdmin,
+    /// The increments exceed the budget of the session.
+    BudgetExceeded { budget: u64, requested: u64 },
+    /// The changes made by a caller application exceed its quota.
+    QuotaExceeded { quota: u64, requested: u64 },
+    /// An operation with an idempotency key wraps another one.
+    NestedIdempotencyKey,
+    /// The named counters could not be loaded or saved.
+    Storage(String),
+}
+
+impl fmt::Display for CounterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CounterError::Overflow => write!(f, "the counter would overflow"),
+            CounterError::Underflow => write!(f, "the counter would go below 0"),
+            CounterError::AboveMaximum { max } => {
+                write!(f, "the counter would exceed its maximum of {max}")
+            }
+            CounterError::BelowMinimum { min } => {
+                write!(f, "the counter would go below its minimum of {min}")
+            }
+            CounterError::InvalidBounds => write!(f, "the minimum is larger than the maximum"),
+            CounterError::InvalidParameters => write!(f, "invalid application parameters"),
+            CounterError::Unauthorized => write!(f, "the signer is not allowed to do this"),
+            CounterError::LastAdmin => write!(f, "the last admin cannot be revoked"),
+            CounterError::NestedIdempotencyKey => {
+                write!(f, "idempotency keys cannot be nested")
+            }
+            CounterError::Storage(error) => {
+                write!(f, "failed to access the named counters: {error}")
+            }
+            CounterError::QuotaExceeded { quota, requested } => write!(
+                f,
+                "the caller changes the counter by {requested} in total, over its quota of {quota}"
+            ),
+            CounterError::BudgetExceeded { budget, requested } => write!(
+                f,
+                "the session increments {requested} in total, over its budget of {budget}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CounterError {}
+
+impl From<ViewError> for CounterError {
+    fn from(error: ViewError) -> Self {
+        CounterError::Storage(error.to_string())
+    }
+}
+
+impl From<CounterError> for linera_sdk::base::Error {
+    fn from(_error: CounterError) -> Self {
+        linera_sdk::base::Error::default()
+    }
+}
+
+impl Counter {
+    /// Sets up a new counter, instantiated on `chain`. The initial value counts as an
+    /// increment made on that chain.
+    pub fn instantiate(
+        &mut self,
+        chain: ChainId,
+        argument: CounterArgument,
+        parameters: CounterParameters,
+    ) -> Result<(), CounterError> {
+        if parameters.step == 0 {
+            return Err(CounterError::InvalidParameters);
+        }
+        self.configure(chain, argument.bounds)?;
+        if argument.initial_value < self.bounds.min.unwrap_or(0) {
+            return Err(self.below_minimum());
+        }
+        if argument.initial_value > self.bounds.max.unwrap_or(u64::MAX) {
+            return Err(self.above_maximum());
+        }
+        self.value = argument.initial_value;
+        self.contributions.entry(chain).or_default().increments = argument.initial_value;
+        self.parameters = Some(parameters);
+        self.owner = argument.owner;
+        self.access = argument.access;
+        if let Some(owner) = argument.owner {
+            self.roles.insert(owner, Role::Admin);
+        }
+        Ok(())
+    }
+
+    /// Checks that `signer` has `permission`. A counter instantiated without an owner has no
+    /// admin, so nobody can administer it.
+    pub fn check_permission(
+        &self,
+        signer: Option<Owner>,
+        permission: Permission,
+    ) -> Result<(), CounterError> {
+        let role = signer.and_then(|signer| self.roles.get(&signer).copied());
+        let allowed = match permission {
+            Permission::Read => self.access != AccessMode::Private || role.is_some(),
+            Permission::Write => self.access == AccessMode::Open || role.is_some(),
+            Permission::Administer => role == Some(Role::Admin),
+        };
+        if !allowed {
+            return Err(CounterError::Unauthorized);
+        }
+        Ok(())
+    }
+
+    /// Grants `role` to `owner`, replacing their previous role. Only admins can do this.
+    pub fn grant_role(
+        &mut self,
+        signer: Option<Owner>,
+        owner: Owner,
+        role: Role,
+    ) -> Result<(), CounterError> {
+        self.check_permission(signer, Permission::Administer)?;
+        if role != Role::Admin && self.roles.get(&owner) == Some(&Role::Admin) && self.admins() == 1
+        {
+            return Err(CounterError::LastAdmin);
+        }
+        self.roles.insert(owner, role);
+        Ok(())
+    }
+
+    /// Revokes the role of `owner`. Only admins can do this.
+    pub fn revoke_role(&mut self, signer: Option<Owner>, owner: Owner) -> Result<(), CounterError> {
+        self.check_permission(signer, Permission::Administer)?;
+        if self.roles.get(&owner) == Some(&Role::Admin) && self.admins() == 1 {
+            return Err(CounterError::LastAdmin);
+        }
+        self.roles.remove(&owner);
+        Ok(())
+    }
+
+    /// Checks that the application `caller` may make a call needing `permission` and changing
+    /// the counter by `amount`, and records the call. Callers without a grant are checked like
+    /// the call's `signer`.
+    pub fn check_caller(
+        &mut self,
+        caller: ApplicationId,
+        signer: Option<Owner>,
+        permission: Permission,
+        amount: u64,
+    ) -> Result<(), CounterError> {
+        let usage = self.caller_usage.get(&caller).copied().unwrap_or_default();
+        let changed = usage.changed.saturating_add(amount);
+        match self.caller_grants.get(&caller) {
+            Some(grant) if grant.permission < permission => return Err(CounterError::Unauthorized),
+            Some(CallerGrant {
+                quota: Some(quota), ..
+            }) if changed > *quota => {
+                return Err(CounterError::QuotaExceeded {
+                    quota: *quota,
+                    requested: changed,
+                })
+            }
+            Some(_) => {}
+            None => self.check_permission(signer, permission)?,
+        }
+        self.caller_usage.insert(
+            caller,
+            CallerUsage {
+                calls: usage.calls + 1,
+                changed,
+            },
+        );
+        Ok(())
+    }
+
+    /// Returns the number of admins.
+    fn admins(&self) -> usize {
+        self.roles
+            .values()
+            .filter(|role| **role == Role::Admin)
+            .count()
+    }
+
+    /// Sets the bounds of the counter, moving its value into them. Moving the value counts as
+    /// a contribution of `chain`.
+    pub fn configure(&mut self, chain: ChainId, bounds: CounterBounds) -> Result<(), CounterError> {
+        let (min, max) = (bounds.min.unwrap_or(0), bounds.max.unwrap_or(u64::MAX));
+        if min > max {
+            return Err(CounterError::InvalidBounds);
+        }
+        self.bounds = bounds;
+        self.set_value(chain, self.value.clamp(min, max));
+        Ok(())
+    }
+
+    /// Resets the counter to its minimum, recording the change as a contribution of `chain`.
+    pub fn reset(&mut self, chain: ChainId) {
+        self.set_value(chain, self.bounds.min.unwrap_or(0));
+    }
+
+    /// Sets the local value to `value`, recording the difference as a contribution of `chain`.
+    fn set_value(&mut self, chain: ChainId, value: u64) {
+        let contribution = self.contributions.entry(chain).or_default();
+        if value > self.value {
+            contribution.increments = contribution.increments.saturating_add(value - self.value);
+        } else {
+            contribution.decrements = contribution.decrements.saturating_add(self.value - value);
+        }
+        self.value = value;
+    }
+
+    /// Opens a session that can increment the counter by up to `budget` in total.
+    pub fn open_session(&mut self, budget: u64) -> Result<CounterSession, CounterError> {
+        let max = self.bounds.max.unwrap_or(u64::MAX);
+        let reserved = self
+            .reserved
+            .checked_add(budget)
+            .filter(|reserved| {
+                self.value
+                    .checked_add(*reserved)
+                    .is_some_and(|value| value <= max)
+            })
+            .ok_or_else(|| self.above_maximum())?;
+        self.reserved = reserved;
+        Ok(CounterSession { budget, pending: 0 })
+    }
+
+    /// Applies the increments of `session`, made on `chain`, and releases the rest of its
+    /// budget.
+    pub fn close_session(
+        &mut self,
+        chain: ChainId,
+        session: &CounterSession,
+    ) -> Result<(), CounterError> {
+        self.reserved -= session.budget;
+        self.increment(chain, session.pending)
+    }
+
+    /// Increments the local value by `value`, recording it as a contribution of `chain`.
+    /// Increments reserved by open sessions count against the maximum.
+    pub fn increment(&mut self, chain: ChainId, value: u64) -> Result<(), CounterError> {
+        let max = self
+            .bounds
+            .max
+            .unwrap_or(u64::MAX)
+            .saturating_sub(self.reserved);
+        let new_value = match self.value.checked_add(value).filter(|value| *value <= max) {
+            Some(new_value) => new_value,
+            None if self.bounds.overflow == Overflow::Saturate => max.max(self.value),
+            None => return Err(self.above_maximum()),
+        };
+        let contribution = self.contributions.entry(chain).or_default();
+        contribution.increments = contribution
+            .increments
+            .saturating_add(new_value - self.value);
+        self.value = new_value;
+        Ok(())
+    }
+
+    /// Decrements the local value by `value`, recording it as a contribution of `chain`.
+    pub fn decrement(&mut self, chain: ChainId, value: u64) -> Result<(), CounterError> {
+        let min = self.bounds.min.unwrap_or(0);
+        let new_value = match self.value.checked_sub(value).filter(|value| *value >= min) {
+            Some(new_value) => new_value,
+            None if self.bounds.overflow == Overflow::Saturate => min.min(self.value),
+            None => return Err(self.below_minimum()),
+        };
+        let contribution = self.contributions.entry(chain).or_default();
+        contribution.decrements = contribution
+            .decrements
+            .saturating_add(self.value - new_value);
+        self.value = new_value;
+        Ok(())
+    }
+
+    /// Returns the error for an operation going over the maximum.
+    fn above_maximum(&self) -> CounterError {
+        match self.bounds.max {
+            Some(max) => CounterError::AboveMaximum { max },
+            None => CounterError::Overflow,
+        }
+    }
+
+    /// Returns the error for an operation going under the minimum.
+    fn below_minimum(&self) -> CounterError {
+        match self.bounds.min {
+            Some(min) => CounterError::BelowMinimum { min },
+            None => CounterError::Underflow,
+        }
+    }
+
+    /// Merges the contributions known to another replica into this one.
+    pub fn merge(&mut self, contributions: &BTreeMap<ChainId, Contribution>) {
+        for (chain, other) in contributions {
+            let known = self.contributions.entry(*chain).or_default();
+            known.increments = known.increments.max(other.increments);
+            known.decrements = known.decrements.max(other.decrements);
+        }
+    }
+
+    /// Returns the increments minus the decrements made on all chains, as far as this replica
+    /// knows, or 0 if there were more decrements.
+    pub fn global_value(&self) -> u64 {
+        let (increments, decrements) = self.contributions.values().fold(
+            (0u64, 0u64),
+            |(increments, decrements), contribution| {
+                (
+                    increments.saturating_add(contribution.increments),
+                    decrements.saturating_add(contribution.decrements),
+                )
+            },
+        );
+        increments.saturating_sub(decrements)
+    }
+
+    /// Adds `chain` to the chains this chain publishes its contributions to.
+    pub fn add_subscriber(&mut self, chain: ChainId) {
+        if !self.subscribers.contains(&chain) {
+            self.subscribers.push(chain);
+        }
+    }
+
+    /// Records the change of the value from `before` made by `actor` at time `now`, if any.
+    pub fn record(&mut self, now: Timestamp, before: u64, actor: Option<Owner>) {
+        if self.value == before {
+            return;
+        }
+        let delta = if self.value > before {
+            i64::try_from(self.value - before).unwrap_or(i64::MAX)
+        } else {
+            i64::try_from(before - self.value).map_or(i64::MIN, |delta| -delta)
+        };
+        if self.history.len() == HISTORY_LIMIT {
+            self.history.pop_front();
+        }
+        self.history.push_back(HistoryEntry {
+            timestamp: now,
+            delta,
+            actor,
+        });
+
+        let minute = Resolution::Minute.micros();
+        let start = now.micros() / minute * minute;
+        let bucket = self.minutes.entry(start).or_insert(Bucket {
+            start,
+            ..Bucket::default()
+        });
+        bucket.changes += 1;
+        if self.value > before {
+            bucket.increase = bucket.increase.saturating_add(self.value - before);
+        } else {
+            bucket.decrease = bucket.decrease.saturating_add(before - self.value);
+        }
+        bucket.close = self.value;
+
+        let now = now.micros();
+        let before = now.saturating_sub(MINUTE_RETENTION);
+        roll_up(&mut self.minutes, &mut self.hours, Resolution::Hour, before);
+        let before = now.saturating_sub(HOUR_RETENTION);
+        roll_up(&mut self.hours, &mut self.days, Resolution::Day, before);
+    }
+
+    /// Returns the changes made from `from` until before `to`, in microseconds, aggregated per
+    /// period of `resolution`. Periods without changes are omitted. Changes that were already
+    /// rolled up into hours or days are returned per hour or day even at a finer resolution.
+    pub fn aggregate(&self, resolution: Resolution, from: u64, to: u64) -> Vec<Bucket> {
+        let mut buckets: Vec<Bucket> = Vec::new();
+        if from >= to {
+            return buckets;
+        }
+        let period = resolution.micros();
+        // The days, hours and minutes cover consecutive spans of time, in this order.
+        let stored = [&self.days, &self.hours, &self.minutes]
+            .into_iter()
+            .flat_map(|buckets| buckets.range(from..to).map(|(_, bucket)| bucket));
+        for stored in stored {
+            let start = stored.start / period * period;
+            match buckets.last_mut() {
+                Some(bucket) if bucket.start == start => bucket.merge(stored),
+                _ => buckets.push(Bucket { start, ..*stored }),
+            }
+        }
+        buckets
+    }
+
+    /// Returns the effects publishing this replica's contributions to every subscriber.
+    fn publish_effects(&self) -> Vec<CounterEffect> {
+        self.subscribers
+            .iter()
+            .map(|chain_id| CounterEffect::Sync {
+                chain_id: *chain_id,
+                contributions: self.contributions.clone(),
+            })
+            .collect()
+    }
+}
+
+/// Merges the buckets of `fine` that start before the period of `resolution` containing
+/// `before` into the buckets of `coarse`, which have periods of `resolution`.
+fn roll_up(
+    fine: &mut BTreeMap<u64, Bucket>,
+    coarse: &mut BTreeMap<u64, Bucket>,
+    resolution: Resolution,
+    before: u64,
+) {
+    let period = resolution.micros();
+    let kept = fine.split_off(&(before / period * period));
+    for bucket in std::mem::replace(fine, kept).into_values() {
+        let start = bucket.start / period * period;
+        coarse
+            .entry(start)
+            .or_insert(Bucket {
+                start,
+                ..Bucket::default()
+            })
+            .merge(&bucket);
+    }
+}
+
+impl CounterSession {
+    /// Increments the session by `value`, within its budget.
+    pub fn increment(&mut self, value: u64) -> Result<(), CounterError> {
+        let requested = self.pending.saturating_add(value);
+        if requested > self.budget {
+            return Err(CounterError::BudgetExceeded {
+                budget: self.budget,
+                requested,
+            });
+        }
+        self.pending = requested;
+        Ok(())
+    }
+}
+
+/// The named counters of the application. They live in their own view, apart from the rest of
+/// the state, so that an increment only loads and saves the key it changes. Named counters are
+/// not bounded and not replicated.
+#[derive(RootView)]
+pub struct KeyedCounters<C> {
+    /// The value of each named counter, by key.
+    pub values: MapView<C, String, u64>,
+}
+
+impl KeyedCounters<ViewStorageContext> {
+    /// Loads the named counters from the application storage.
+    pub async fn load_stored() -> Result<Self, CounterError> {
+        Ok(Self::load(ViewStorageContext::default()).await?)
+    }
+}
+
+impl<C> KeyedCounters<C>
+where
+    C: Context + Send + Sync + Clone + 'static,
+    ViewError: From<C::Error>,
+{
+    /// Increments the counter named `key` by `value`, creating it if needed, and returns its
+    /// new value.
+    pub async fn increment(&mut self, key: String, value: u64) -> Result<u64, CounterError> {
+        let current = self.values.get(&key).await?.unwrap_or_default();
+        let value = current.checked_add(value).ok_or(CounterError::Overflow)?;
+        self.values.insert(&key, value)?;
+        Ok(value)
+    }
+
+    /// Returns the value of the counter named `key`, or 0 if it was never incremented.
+    pub async fn value(&self, key: &str) -> Result<u64, CounterError> {
+        Ok(self.values.get(&key.to_owned()).await?.unwrap_or_default())
+    }
+
+    /// Returns the named counters whose key starts with `prefix`, sorted by key.
+    pub async fn with_prefix(&self, prefix: &str) -> Result<Vec<(String, u64)>, CounterError> {
+        let mut counters = self.matching(|key| key.starts_with(prefix)).await?;
+        counters.sort();
+        Ok(counters)
+    }
+
+    /// Returns the `n` largest named counters, largest first. Ties are sorted by key. This
+    /// reads every named counter.
+    pub async fn top(&self, n: usize) -> Result<Vec<(String, u64)>, CounterError> {
+        let mut counters = self.matching(|_| true).await?;
+        counters.sort_by_key(|(key, value)| (Reverse(*value), key.clone()));
+        counters.truncate(n);
+        Ok(counters)
+    }
+
+    /// Returns the named counters whose key satisfies `filter`, in storage order.
+    async fn matching(
+        &self,
+        mut filter: impl FnMut(&str) -> bool + Send,
+    ) -> Result<Vec<(String, u64)>, CounterError> {
+        let mut counters = Vec::new();
+        self.values
+            .for_each_index_value(|key, value| {
+                if filter(&key) {
+                    counters.push((key, value));
+                }
+                Ok(())
+            })
+            .await?;
+        Ok(counters)
+    }
 }
 
 /// The operation types that can be sent to the counter application.
 #[derive(Debug, Deserialize, Serialize)]
 pub enum CounterOperation {
-    /// Increments the counter value by 1.
+    /// Increments the counter value by the step.
     Increment,
     /// Increments the counter value by the specified amount.
     IncrementBy { value: u64 },
-    /// Resets the counter to 0.
+    /// Decrements the counter value by the step.
+    Decrement,
+    /// Decrements the counter value by the specified amount.
+    DecrementBy { value: u64 },
+    /// Resets the local counter value to its minimum, recording the change as a decrement
+    /// made on this chain.
     Reset,
+    /// Changes the bounds and access mode of the counter.
+    Configure {
+        bounds: CounterBounds,
+        access: AccessMode,
+    },
+    /// Grants `role` to `owner`, replacing their previous role.
+    GrantRole { owner: Owner, role: Role },
+    /// Revokes the role of `owner`.
+    RevokeRole { owner: Owner },
+    /// Grants `permission` to the caller application `application_id`, limiting the total
+    /// amount it may change counters by to `quota`. This replaces its previous grant, but not
+    /// what it already used.
+    GrantCaller {
+        application_id: ApplicationId,
+        permission: Permission,
+        quota: Option<u64>,
+    },
+    /// Revokes the grant of the caller application `application_id`.
+    RevokeCaller { application_id: ApplicationId },
+    /// Executes `operation` unless the signer already submitted an operation with `key`
+    /// recently. Lets clients retry operations without applying them twice.
+    Idempotent {
+        key: String,
+        operation: Box<CounterOperation>,
+    },
+    /// Subscribes this chain to the contributions published by `chain_id`. This only needs
+    /// write permission, as chains other than the one the counter was created on have no
+    /// admin.
+    Subscribe { chain_id: ChainId },
+    /// Publishes the contributions known to this chain to every subscriber.
+    Publish,
+    /// Increments the counter named `key` by the step.
+    IncrementKey { key: String },
+    /// Increments the counter named `key` by the specified amount.
+    IncrementKeyBy { key: String, value: u64 },
+}
+
+impl CounterOperation {
+    /// Returns what the signer must be allowed to do to execute the operation.
+    pub fn permission(&self) -> Permission {
+        match self {
+            CounterOperation::Increment
+            | CounterOperation::IncrementBy { .. }
+            | CounterOperation::Decrement
+            | CounterOperation::DecrementBy { .. }
+            | CounterOperation::Publish
+            | CounterOperation::IncrementKey { .. }
+            | CounterOperation::IncrementKeyBy { .. }
+            | CounterOperation::Subscribe { .. } => Permission::Write,
+            CounterOperation::Reset
+            | CounterOperation::Configure { .. }
+            | CounterOperation::GrantRole { .. }
+            | CounterOperation::RevokeRole { .. }
+            | CounterOperation::GrantCaller { .. }
+            | CounterOperation::RevokeCaller { .. } => Permission::Administer,
+            CounterOperation::Idempotent { operation, .. } => operation.permission(),
+        }
+    }
 }
 
 /// The message types that can be sent to the counter application.
 #[derive(Debug, Deserialize, Serialize)]
 pub enum CounterMessage {
-    /// Increments the counter value by 1.
+    /// Increments the counter value by the step.
     Increment,
     /// Increments the counter value by the specified amount.
     IncrementBy { value: u64 },
+    /// Decrements the counter value by the step.
+    Decrement,
+    /// Decrements the counter value by the specified amount.
+    DecrementBy { value: u64 },
+    /// Subscribes the sending chain to the contributions published by this chain.
+    Subscribe,
+    /// Merges the contributions known to the sending chain.
+    Sync {
+        contributions: BTreeMap<ChainId, Contribution>,
+    },
 }
 
-/// The application call types that can be made to the counter application.
-#[derive(Debug, Deserialize, Serialize)]
-pub enum CounterApplicationCall {
-    /// Increments the counter value by 1.
-    Increment,
-    /// Increments the counter value by the specified amount.
-    IncrementBy { value: u64 },
-    /// Gets the current counter value.
-    GetValue,
+impl CounterMessage {
+    /// Returns what the signer must be allowed to do for the message to be executed, if
+    /// anything. Replication messages are accepted from any chain.
+    pub fn permission(&self) -> Option<Permission> {
+        match self {
+            CounterMessage::Subscribe | CounterMessage::Sync { .. } => None,
+            _ => Some(Permission::Write),
+        }
+    }
 }
 
 /// The session call types that can be made to the counter application.
 #[derive(Debug, Deserialize, Serialize)]
 pub enum CounterSessionCall {
-    /// Increments the counter value by 1.
+    /// Increments the counter value by the step when the session is closed.
     Increment,
-    /// Increments the counter value by the specified amount.
+    /// Increments the counter value by the specified amount when the session is closed.
     IncrementBy { value: u64 },
-    /// Gets the current counter value.
+    /// Gets the current counter value, without the session's pending increments.
     GetValue,
+    /// Applies the session's increments and closes it.
+    Close,
 }
 
 /// The effect types that can be sent by the counter application.
@@ -62,6 +854,113 @@ pub enum CounterEffect {
     Increment,
     /// Increments the counter value by the specified amount.
     IncrementBy { value: u64 },
+    /// Asks `chain_id` to publish its contributions to this chain.
+    Subscribe { chain_id: ChainId },
+    /// Sends the contributions known to this chain to `chain_id`.
+    Sync {
+        chain_id: ChainId,
+        contributions: BTreeMap<ChainId, Contribution>,
+    },
+}
+
+impl Counter {
+    /// Returns the step the application was published with. The parameters are read on the
+    /// first call on each chain, since only the chain that instantiated the counter stored them.
+    fn step(&mut self) -> Result<u64, CounterError> {
+        let parameters = match self.parameters {
+            Some(parameters) => parameters,
+            None => Self::read_parameters()?,
+        };
+        self.parameters = Some(parameters);
+        Ok(parameters.step)
+    }
+
+    /// Parses the parameters the application was published with.
+    fn read_parameters() -> Result<CounterParameters, CounterError> {
+        serde_json::from_slice(&system_api::current_application_parameters())
+            .map_err(|_| CounterError::InvalidParameters)
+    }
+
+    /// Increments the named counter `key` by `value` in the application storage, returning its
+    /// new value.
+    async fn increment_key(key: String, value: u64) -> Result<u64, CounterError> {
+        let mut keyed = KeyedCounters::load_stored().await?;
+        let value = keyed.increment(key, value).await?;
+        keyed.save().await?;
+        Ok(value)
+    }
+
+    /// Executes `operation` on behalf of its signer, once its permission was checked.
+    async fn apply_operation(
+        &mut self,
+        context: &OperationContext,
+        operation: CounterOperation,
+    ) -> Result<ExecutionResult<CounterEffect>, linera_sdk::base::Error> {
+        let step = self.step()?;
+        match operation {
+            CounterOperation::Increment => {
+                self.increment(context.chain_id, step)?;
+                Ok(ExecutionResult::default())
+            }
+            CounterOperation::IncrementBy { value } => {
+                self.increment(context.chain_id, value)?;
+                Ok(ExecutionResult::default())
+            }
+            CounterOperation::Decrement => {
+                self.decrement(context.chain_id, step)?;
+                Ok(ExecutionResult::default())
+            }
+            CounterOperation::DecrementBy { value } => {
+                self.decrement(context.chain_id, value)?;
+                Ok(ExecutionResult::default())
+            }
+            CounterOperation::Reset => {
+                self.reset(context.chain_id);
+                Ok(ExecutionResult::default())
+            }
+            CounterOperation::Configure { bounds, access } => {
+                self.configure(context.chain_id, bounds)?;
+                self.access = access;
+                Ok(ExecutionResult::default())
+            }
+            CounterOperation::GrantRole { owner, role } => {
+                self.grant_role(context.authenticated_signer, owner, role)?;
+                Ok(ExecutionResult::default())
+            }
+            CounterOperation::RevokeRole { owner } => {
+                self.revoke_role(context.authenticated_signer, owner)?;
+                Ok(ExecutionResult::default())
+            }
+            CounterOperation::GrantCaller {
+                application_id,
+                permission,
+                quota,
+            } => {
+                let grant = CallerGrant { permission, quota };
+                self.caller_grants.insert(application_id, grant);
+                Ok(ExecutionResult::default())
+            }
+            CounterOperation::RevokeCaller { application_id } => {
+                self.caller_grants.remove(&application_id);
+                Ok(ExecutionResult::default())
+            }
+            CounterOperation::Subscribe { chain_id } => Ok(ExecutionResult {
+                effects: vec![CounterEffect::Subscribe { chain_id }],
+            }),
+            CounterOperation::Publish => Ok(ExecutionResult {
+                effects: self.publish_effects(),
+            }),
+            CounterOperation::IncrementKey { key } => {
+                Self::increment_key(key, step).await?;
+                Ok(ExecutionResult::default())
+            }
+            CounterOperation::IncrementKeyBy { key, value } => {
+                Self::increment_key(key, value).await?;
+                Ok(ExecutionResult::default())
+            }
+            CounterOperation::Idempotent { .. } => Err(CounterError::NestedIdempotencyKey.into()),
+        }
+    }
 }
 
 impl Contract for Counter {
@@ -70,97 +969,276 @@ impl Contract for Counter {
     type ApplicationCall = CounterApplicationCall;
     type SessionCall = CounterSessionCall;
     type Effect = CounterEffect;
-    type SessionState = ();
+    type SessionState = CounterSession;
     type ApplicationState = Counter;
 
     async fn initialize(
         &mut self,
-        _context: &OperationContext,
-        _argument: (),
+        context: &OperationContext,
+        argument: CounterArgument,
         _storage: ViewStateStorage<Self>,
     ) -> Result<ExecutionResult<Self::Effect>, linera_sdk::base::Error> {
+        let parameters = Self::read_parameters()?;
+        self.instantiate(context.chain_id, argument, parameters)?;
         Ok(ExecutionResult::default())
     }
 
     async fn execute_operation(
         &mut self,
-        _context: &OperationContext,
+        context: &OperationContext,
         operation: Self::Operation,
         _storage: ViewStateStorage<Self>,
     ) -> Result<ExecutionResult<Self::Effect>, linera_sdk::base::Error> {
-        match operation {
-            CounterOperation::Increment => {
-                self.value += 1;
-                Ok(ExecutionResult::default())
-            }
-            CounterOperation::IncrementBy { value } => {
-                self.value += value;
-                Ok(ExecutionResult::default())
-            }
-            CounterOperation::Reset => {
-                self.value = 0;
-                Ok(ExecutionResult::default())
+        let signer = context.authenticated_signer;
+        let (idempotency_key, operation) = match operation {
+            CounterOperation::Idempotent { key, operation } => (Some(key), *operation),
+            operation => (None, operation),
+        };
+        if let Some(key) = &idempotency_key {
+            if self.idempotency.get(&signer, key).is_some() {
+                // A retry of an operation that was already executed.
+                return Ok(ExecutionResult::default());
             }
         }
+        self.check_permission(signer, operation.permission())?;
+        let keyed = match &operation {
+            CounterOperation::IncrementKey { key }
+            | CounterOperation::IncrementKeyBy { key, .. } => Some(key.clone()),
+            _ => None,
+        };
+        let before = self.value;
+        let result = self.apply_operation(context, operation).await?;
+        self.record(system_api::current_system_time(), before, signer);
+        if let Some(key) = idempotency_key {
+            let value = match keyed {
+                Some(keyed) => KeyedCounters::load_stored().await?.value(&keyed).await?,
+                None => self.value,
+            };
+            self.idempotency.insert(signer, key, value);
+        }
+        Ok(result)
     }
 
     async fn execute_message(
         &mut self,
-        _context: &MessageContext,
+        context: &MessageContext,
         message: Self::Message,
         _storage: ViewStateStorage<Self>,
     ) -> Result<ExecutionResult<Self::Effect>, linera_sdk::base::Error> {
-        match message {
+        if let Some(permission) = message.permission() {
+            self.check_permission(context.authenticated_signer, permission)?;
+        }
+        let step = self.step()?;
+        let before = self.value;
+        let result = match message {
             CounterMessage::Increment => {
-                self.value += 1;
+                self.increment(context.chain_id, step)?;
                 Ok(ExecutionResult::default())
             }
             CounterMessage::IncrementBy { value } => {
-                self.value += value;
+                self.increment(context.chain_id, value)?;
                 Ok(ExecutionResult::default())
             }
-        }
+            CounterMessage::Decrement => {
+                self.decrement(context.chain_id, step)?;
+                Ok(ExecutionResult::default())
+            }
+            CounterMessage::DecrementBy { value } => {
+                self.decrement(context.chain_id, value)?;
+                Ok(ExecutionResult::default())
+            }
+            CounterMessage::Subscribe => {
+                let subscriber = context.message_id.chain_id;
+                self.add_subscriber(subscriber);
+                // Bring the new subscriber up to date right away.
+                let effects = vec![CounterEffect::Sync {
+                    chain_id: subscriber,
+                    contributions: self.contributions.clone(),
+                }];
+                Ok(ExecutionResult { effects })
+            }
+            CounterMessage::Sync { contributions } => {
+                self.merge(&contributions);
+                Ok(ExecutionResult::default())
+            }
+        };
+        self.record(
+            system_api::current_system_time(),
+            before,
+            context.authenticated_signer,
+        );
+        result
     }
 
     async fn handle_application_call(
         &mut self,
-        _context: &CalleeContext,
+        context: &CalleeContext,
         call: Self::ApplicationCall,
         _storage: ViewStateStorage<Self>,
     ) -> Result<ApplicationCallResult<Self::Effect>, linera_sdk::base::Error> {
-        match call {
+        let step = self.step()?;
+        let permission = call.permission();
+        match context.authenticated_caller_id {
+            Some(caller) => {
+                let amount = call.amount(step);
+                self.check_caller(caller, context.authenticated_signer, permission, amount)?;
+            }
+            None => self.check_permission(context.authenticated_signer, permission)?,
+        }
+        let before = self.value;
+        let result = match call {
             CounterApplicationCall::Increment => {
-                self.value += 1;
-                Ok(ApplicationCallResult::default())
+                self.increment(context.chain_id, step)?;
+                Ok(ApplicationCallResult {
+                    value: Some(self.value),
+                    effects: vec![],
+                })
             }
             CounterApplicationCall::IncrementBy { value } => {
-                self.value += value;
-                Ok(ApplicationCallResult::default())
+                self.increment(context.chain_id, value)?;
+                Ok(ApplicationCallResult {
+                    value: Some(self.value),
+                    effects: vec![],
+                })
+            }
+            CounterApplicationCall::Decrement => {
+                self.decrement(context.chain_id, step)?;
+                Ok(ApplicationCallResult {
+                    value: Some(self.value),
+                    effects: vec![],
+                })
             }
+            CounterApplicationCall::DecrementBy { value } => {
+                self.decrement(context.chain_id, value)?;
+                Ok(ApplicationCallResult {
+                    value: Some(self.value),
+                    effects: vec![],
+                })
+            }
+            CounterApplicationCall::IncrementKey { key } => Ok(ApplicationCallResult {
+                value: Some(Self::increment_key(key, step).await?),
+                effects: vec![],
+            }),
+            CounterApplicationCall::IncrementKeyBy { key, value } => Ok(ApplicationCallResult {
+                value: Some(Self::increment_key(key, value).await?),
+                effects: vec![],
+            }),
             CounterApplicationCall::GetValue => {
                 Ok(ApplicationCallResult {
                     value: Some(self.value),
                     effects: vec![],
                 })
             }
-        }
+            CounterApplicationCall::GetGlobalValue => Ok(ApplicationCallResult {
+                value: Some(self.global_value()),
+                effects: vec![],
+            }),
+            CounterApplicationCall::OpenSession { budget } => {
+                let session = self.open_session(budget)?;
+                Ok(ApplicationCallResult {
+                    create_sessions: vec![session],
+                    ..Default::default()
+                })
+            }
+            CounterApplicationCall::GetKey { key } => Ok(ApplicationCallResult {
+                value: Some(KeyedCounters::load_stored().await?.value(&key).await?),
+                effects: vec![],
+            }),
+            CounterApplicationCall::GetKeysWithPrefix { prefix } => Ok(ApplicationCallResult {
+                value: Some(
+                    KeyedCounters::load_stored()
+                        .await?
+                        .with_prefix(&prefix)
+                        .await?,
+                ),
+                effects: vec![],
+            }),
+            CounterApplicationCall::GetTopKeys { n } => Ok(ApplicationCallResult {
+                value: Some(KeyedCounters::load_stored().await?.top(n).await?),
+                effects: vec![],
+            }),
+            CounterApplicationCall::GetHistory => Ok(ApplicationCallResult {
+                value: Some(self.history.clone()),
+                effects: vec![],
+            }),
+            CounterApplicationCall::GetAggregates {
+                resolution,
+                from,
+                to,
+            } => Ok(ApplicationCallResult {
+                value: Some(self.aggregate(resolution, from, to)),
+                effects: vec![],
+            }),
+        };
+        self.record(
+            system_api::current_system_time(),
+            before,
+            context.authenticated_signer,
+        );
+        result
     }
 
     async fn handle_session_call(
         &mut self,
-        _context: &CalleeContext,
-        _call: Self::SessionCall,
+        context: &CalleeContext,
+        session: &mut Self::SessionState,
+        call: Self::SessionCall,
         _storage: ViewStateStorage<Self>,
     ) -> Result<SessionCallResult<Self::Effect>, linera_sdk::base::Error> {
-        Err(linera_sdk::base::Error::default())
+        let step = self.step()?;
+        let before = self.value;
+        let result = match call {
+            CounterSessionCall::Increment => {
+                session.increment(step)?;
+                Ok(SessionCallResult::default())
+            }
+            CounterSessionCall::IncrementBy { value } => {
+                session.increment(value)?;
+                Ok(SessionCallResult::default())
+            }
+            CounterSessionCall::GetValue => Ok(SessionCallResult {
+                inner: ApplicationCallResult {
+                    value: Some(self.value),
+                    effects: vec![],
+                },
+                close_session: false,
+            }),
+            CounterSessionCall::Close => {
+                self.close_session(context.chain_id, session)?;
+                Ok(SessionCallResult {
+                    close_session: true,
+                    ..Default::default()
+                })
+            }
+        };
+        self.record(
+            system_api::current_system_time(),
+            before,
+            context.authenticated_signer,
+        );
+        result
     }
 
     async fn handle_effect(
         &mut self,
         _context: &EffectContext,
-        _effect: Self::Effect,
+        effect: Self::Effect,
         _storage: ViewStateStorage<Self>,
     ) -> Result<ExecutionResult<Self::Effect>, linera_sdk::base::Error> {
+        // Replication messages need no signer, so they are sent unauthenticated.
+        match effect {
+            CounterEffect::Subscribe { chain_id } => {
+                system_api::send_message(false, chain_id, &CounterMessage::Subscribe);
+            }
+            CounterEffect::Sync {
+                chain_id,
+                contributions,
+            } => {
+                let message = CounterMessage::Sync { contributions };
+                system_api::send_message(false, chain_id, &message);
+            }
+            CounterEffect::Increment | CounterEffect::IncrementBy { .. } => {}
+        }
         Ok(ExecutionResult::default())
     }
 }
@@ -170,6 +1248,40 @@ impl Contract for Counter {
 pub struct CounterView {
     /// The current value of the counter.
     pub value: u64,
+    /// The increments reserved by open sessions, not yet applied to `value`.
+    pub reserved: u64,
+    /// The range the value is kept in, and what happens to operations leaving it.
+    pub bounds: CounterBounds,
+    /// The parameters the application was published with, once this chain has read them.
+    pub parameters: Option<CounterParameters>,
+    /// The owner who instantiated the counter, and its first admin.
+    pub owner: Option<Owner>,
+    /// Who may change and read the counter.
+    pub access: AccessMode,
+    /// The role granted to each owner.
+    pub roles: BTreeMap<Owner, Role>,
+    /// The increments and decrements made on each chain, as far as this replica knows. Both
+    /// only ever grow, so replicas converge by taking the largest totals of each chain.
+    pub contributions: BTreeMap<ChainId, Contribution>,
+    /// The chains this chain publishes its contributions to.
+    pub subscribers: Vec<ChainId>,
+    /// The latest changes of the counter value, oldest first, at most [`HISTORY_LIMIT`].
+    pub history: VecDeque<HistoryEntry>,
+    /// The changes of the counter value aggregated per minute, by the start of the minute in
+    /// microseconds, for the last [`MINUTE_RETENTION`].
+    pub minutes: BTreeMap<u64, Bucket>,
+    /// The older changes aggregated per hour, by the start of the hour, for the last
+    /// [`HOUR_RETENTION`].
+    pub hours: BTreeMap<u64, Bucket>,
+    /// The older changes aggregated per day, by the start of the day. Unlike `history`, these
+    /// are never dropped.
+    pub days: BTreeMap<u64, Bucket>,
+    /// The permission and quota granted to each caller application.
+    pub caller_grants: BTreeMap<ApplicationId, CallerGrant>,
+    /// The calls made by each caller application.
+    pub caller_usage: BTreeMap<ApplicationId, CallerUsage>,
+    /// The latest idempotency keys of each signer, with the value their operation resulted in.
+    pub idempotency: IdempotencyWindow<Option<Owner>, u64>,
 }
 
 impl linera_sdk::contract::WitInterface for Counter {
@@ -179,6 +1291,8 @@ impl linera_sdk::contract::WitInterface for Counter {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use futures::executor::block_on;
+    use linera_views::memory::create_memory_context;
 
     #[test]
     fn test_counter_increment() {
@@ -200,10 +1314,409 @@ mod tests {
 
     #[test]
     fn test_counter_reset() {
-        let mut counter = Counter { value: 10 };
+        let mut counter = Counter {
+            value: 10,
+            ..Counter::default()
+        };
         assert_eq!(counter.value, 10);
         
         counter.value = 0;
         assert_eq!(counter.value, 0);
     }
+
+    #[test]
+    fn test_counter_session_budget() {
+        let mut counter = Counter {
+            value: u64::MAX - 10,
+            ..Counter::default()
+        };
+        let mut session = counter.open_session(6).unwrap();
+        assert_eq!(counter.open_session(5), Err(CounterError::Overflow));
+
+        session.increment(1).unwrap();
+        session.increment(4).unwrap();
+        assert_eq!(
+            session.increment(2),
+            Err(CounterError::BudgetExceeded {
+                budget: 6,
+                requested: 7
+            })
+        );
+        assert_eq!(counter.value, u64::MAX - 10);
+
+        counter
+            .close_session(ChainId::from([1; 32]), &session)
+            .unwrap();
+        assert_eq!(counter.value, u64::MAX - 5);
+        assert_eq!(counter.reserved, 0);
+        assert!(counter.open_session(5).is_ok());
+    }
+
+    #[test]
+    fn test_replicas_converge() {
+        let (chain_a, chain_b) = (ChainId::from([1; 32]), ChainId::from([2; 32]));
+        let mut a = Counter::default();
+        let mut b = Counter::default();
+        a.increment(chain_a, 3).unwrap();
+        b.increment(chain_b, 5).unwrap();
+        b.decrement(chain_b, 1).unwrap();
+        b.merge(&a.contributions);
+        a.increment(chain_a, 1).unwrap();
+
+        // Merging is idempotent and order-independent.
+        a.merge(&b.contributions);
+        b.merge(&a.contributions);
+        b.merge(&a.contributions);
+        assert_eq!(a.global_value(), 8);
+        assert_eq!(b.global_value(), 8);
+        assert_eq!((a.value, b.value), (4, 4));
+
+        a.value = 0;
+        assert_eq!(a.global_value(), 8);
+    }
+
+    #[test]
+    fn test_bounded_counter() {
+        let chain = ChainId::from([1; 32]);
+        let mut counter = Counter::default();
+        assert_eq!(counter.decrement(chain, 1), Err(CounterError::Underflow));
+
+        let bounds = CounterBounds {
+            min: Some(2),
+            max: Some(10),
+            overflow: Overflow::Error,
+        };
+        counter.configure(chain, bounds.clone()).unwrap();
+        assert_eq!(counter.value, 2);
+        counter.increment(chain, 8).unwrap();
+        assert_eq!(
+            counter.increment(chain, 1),
+            Err(CounterError::AboveMaximum { max: 10 })
+        );
+        counter.decrement(chain, 7).unwrap();
+        assert_eq!(
+            counter.decrement(chain, 2),
+            Err(CounterError::BelowMinimum { min: 2 })
+        );
+        assert_eq!(counter.value, 3);
+
+        counter
+            .configure(
+                chain,
+                CounterBounds {
+                    overflow: Overflow::Saturate,
+                    ..bounds
+                },
+            )
+            .unwrap();
+        counter.increment(chain, u64::MAX).unwrap();
+        assert_eq!(counter.value, 10);
+        counter.decrement(chain, 100).unwrap();
+        assert_eq!(counter.value, 2);
+        assert_eq!(
+            counter.contributions[&chain],
+            Contribution {
+                increments: 17,
+                decrements: 15
+            }
+        );
+        assert_eq!(counter.global_value(), 2);
+        counter.increment(chain, 5).unwrap();
+        counter.reset(chain);
+        assert_eq!(counter.value, 2);
+        assert_eq!(counter.global_value(), 2);
+
+        let inverted = CounterBounds {
+            min: Some(5),
+            max: Some(4),
+            overflow: Overflow::Error,
+        };
+        assert_eq!(
+            counter.configure(chain, inverted),
+            Err(CounterError::InvalidBounds)
+        );
+    }
+
+    #[test]
+    fn test_instantiation() {
+        let chain = ChainId::from([1; 32]);
+        let owner = Owner::from([3; 32]);
+        let argument = CounterArgument {
+            initial_value: 50,
+            bounds: CounterBounds {
+                max: Some(100),
+                ..CounterBounds::default()
+            },
+            owner: Some(owner),
+            access: AccessMode::Private,
+        };
+        let mut counter = Counter::default();
+        assert_eq!(
+            counter.instantiate(chain, argument.clone(), CounterParameters { step: 0 }),
+            Err(CounterError::InvalidParameters)
+        );
+        let too_high = CounterArgument {
+            initial_value: 101,
+            ..argument.clone()
+        };
+        assert_eq!(
+            counter.instantiate(chain, too_high, CounterParameters::default()),
+            Err(CounterError::AboveMaximum { max: 100 })
+        );
+
+        let mut counter = Counter::default();
+        counter
+            .instantiate(chain, argument, CounterParameters { step: 5 })
+            .unwrap();
+        assert_eq!(counter.value, 50);
+        assert_eq!(counter.global_value(), 50);
+        assert_eq!(counter.parameters, Some(CounterParameters { step: 5 }));
+        assert_eq!(counter.roles.get(&owner), Some(&Role::Admin));
+    }
+
+    #[test]
+    fn test_roles() {
+        let (admin, incrementer, stranger) = (
+            Owner::from([1; 32]),
+            Owner::from([2; 32]),
+            Owner::from([3; 32]),
+        );
+        let mut counter = Counter::default();
+        // Without any admin, nobody can take the counter over, but replicas can subscribe.
+        assert_eq!(
+            counter.grant_role(Some(stranger), stranger, Role::Admin),
+            Err(CounterError::Unauthorized)
+        );
+        let subscribe = CounterOperation::Subscribe {
+            chain_id: ChainId::from([2; 32]),
+        };
+        assert_eq!(
+            counter.check_permission(Some(stranger), subscribe.permission()),
+            Ok(())
+        );
+        let argument = CounterArgument {
+            owner: Some(admin),
+            access: AccessMode::PublicRead,
+            ..CounterArgument::default()
+        };
+        counter
+            .instantiate(
+                ChainId::from([1; 32]),
+                argument,
+                CounterParameters::default(),
+            )
+            .unwrap();
+        assert_eq!(
+            counter.grant_role(Some(stranger), stranger, Role::Admin),
+            Err(CounterError::Unauthorized)
+        );
+        counter
+            .grant_role(Some(admin), incrementer, Role::Incrementer)
+            .unwrap();
+
+        let allowed =
+            |signer, permission| counter.check_permission(Some(signer), permission).is_ok();
+        assert!(allowed(incrementer, Permission::Write));
+        assert!(!allowed(incrementer, Permission::Administer));
+        assert!(!allowed(stranger, Permission::Write));
+        assert!(allowed(stranger, Permission::Read));
+        assert!(allowed(admin, Permission::Administer));
+
+        assert_eq!(
+            counter.revoke_role(Some(admin), admin),
+            Err(CounterError::LastAdmin)
+        );
+        counter.revoke_role(Some(admin), incrementer).unwrap();
+        assert_eq!(
+            counter.check_permission(Some(incrementer), Permission::Write),
+            Err(CounterError::Unauthorized)
+        );
+        counter.access = AccessMode::Private;
+        assert_eq!(
+            counter.check_permission(Some(stranger), Permission::Read),
+            Err(CounterError::Unauthorized)
+        );
+    }
+
+    #[test]
+    fn test_keyed_counters() {
+        block_on(async {
+            let context = create_memory_context();
+            let mut keyed = KeyedCounters::load(context.clone()).await.unwrap();
+            assert_eq!(keyed.increment("/blog/a".into(), 3).await, Ok(3));
+            assert_eq!(keyed.increment("/blog/b".into(), 5).await, Ok(5));
+            assert_eq!(keyed.increment("/about".into(), 5).await, Ok(5));
+            assert_eq!(keyed.increment("/blog/a".into(), 1).await, Ok(4));
+            assert_eq!(
+                keyed.increment("/blog/a".into(), u64::MAX).await,
+                Err(CounterError::Overflow)
+            );
+
+            assert_eq!(keyed.value("/blog/a").await, Ok(4));
+            assert_eq!(keyed.value("/missing").await, Ok(0));
+            assert_eq!(
+                keyed.with_prefix("/blog/").await,
+                Ok(vec![("/blog/a".to_string(), 4), ("/blog/b".to_string(), 5)])
+            );
+            assert_eq!(
+                keyed.top(2).await,
+                Ok(vec![("/about".to_string(), 5), ("/blog/b".to_string(), 5)])
+            );
+            keyed.increment("/blog/a".into(), 2).await.unwrap();
+            keyed.save().await.unwrap();
+
+            let keyed = KeyedCounters::load(context).await.unwrap();
+            assert_eq!(keyed.top(1).await, Ok(vec![("/blog/a".to_string(), 6)]));
+        });
+    }
+
+    #[test]
+    fn test_history_aggregates() {
+        let actor = Some(Owner::from([1; 32]));
+        let minute = Resolution::Minute.micros();
+        let hour = Resolution::Hour.micros();
+        let mut counter = Counter::default();
+        for (micros, value) in [
+            (0, 5),
+            (minute / 2, 3),
+            (minute, 10),
+            (hour + 1, 10),
+            (hour + 2, 0),
+        ] {
+            let before = counter.value;
+            counter.value = value;
+            counter.record(Timestamp::from(micros), before, actor);
+        }
+
+        // The unchanged value at `hour + 1` is not recorded.
+        assert_eq!(counter.history.len(), 4);
+        assert_eq!(counter.history[1].delta, -2);
+        assert_eq!(counter.history[3].delta, -10);
+        assert_eq!(counter.history[3].actor, actor);
+
+        let minutes = counter.aggregate(Resolution::Minute, 0, hour);
+        assert_eq!(minutes.len(), 2);
+        assert_eq!(
+            (minutes[0].increase, minutes[0].decrease, minutes[0].close),
+            (5, 2, 3)
+        );
+        let hours = counter.aggregate(Resolution::Hour, 0, u64::MAX);
+        assert_eq!(
+            hours,
+            vec![
+                Bucket {
+                    start: 0,
+                    changes: 3,
+                    increase: 12,
+                    decrease: 2,
+                    close: 10
+                },
+                Bucket {
+                    start: hour,
+                    changes: 1,
+                    increase: 0,
+                    decrease: 10,
+                    close: 0
+                },
+            ]
+        );
+        assert_eq!(
+            counter.aggregate(Resolution::Day, 0, u64::MAX)[0].changes,
+            4
+        );
+
+        // Old minutes are rolled up into hours, and old hours into days.
+        let day = Resolution::Day.micros();
+        let before = counter.value;
+        counter.value = 7;
+        counter.record(Timestamp::from(40 * day), before, actor);
+        assert_eq!(counter.minutes.len(), 1);
+        assert!(counter.hours.is_empty());
+        assert_eq!(counter.days.len(), 1);
+        let minutes = counter.aggregate(Resolution::Minute, 0, u64::MAX);
+        assert_eq!(
+            minutes,
+            vec![
+                Bucket {
+                    start: 0,
+                    changes: 4,
+                    increase: 12,
+                    decrease: 12,
+                    close: 0
+                },
+                Bucket {
+                    start: 40 * day,
+                    changes: 1,
+                    increase: 7,
+                    decrease: 0,
+                    close: 7
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_caller_grants() {
+        let (poll, tip_jar, stranger) = (
+            ApplicationId::from([1; 32]),
+            ApplicationId::from([2; 32]),
+            ApplicationId::from([3; 32]),
+        );
+        let signer = Some(Owner::from([1; 32]));
+        let mut counter = Counter {
+            access: AccessMode::PublicRead,
+            ..Counter::default()
+        };
+        counter.caller_grants.insert(
+            poll,
+            CallerGrant {
+                permission: Permission::Write,
+                quota: Some(3),
+            },
+        );
+        counter.caller_grants.insert(
+            tip_jar,
+            CallerGrant {
+                permission: Permission::Read,
+                quota: None,
+            },
+        );
+
+        assert_eq!(
+            counter.check_caller(poll, signer, Permission::Write, 2),
+            Ok(())
+        );
+        assert_eq!(
+            counter.check_caller(poll, signer, Permission::Write, 2),
+            Err(CounterError::QuotaExceeded {
+                quota: 3,
+                requested: 4
+            })
+        );
+        assert_eq!(
+            counter.check_caller(poll, signer, Permission::Read, 0),
+            Ok(())
+        );
+        assert_eq!(
+            counter.caller_usage[&poll],
+            CallerUsage {
+                calls: 2,
+                changed: 2
+            }
+        );
+        assert_eq!(
+            counter.check_caller(tip_jar, signer, Permission::Write, 1),
+            Err(CounterError::Unauthorized)
+        );
+        // Callers without a grant are checked like their signer.
+        assert_eq!(
+            counter.check_caller(stranger, signer, Permission::Write, 1),
+            Err(CounterError::Unauthorized)
+        );
+        assert_eq!(
+            counter.check_caller(stranger, signer, Permission::Read, 0),
+            Ok(())
+        );
+        assert_eq!(counter.caller_usage[&stranger].calls, 1);
+    }
 }
@@ -0,0 +1,178 @@
+use crate::{
+    AccessMode, Bucket, CallerUsage, Counter, CounterBounds, CounterOperation, HistoryEntry,
+    KeyedCounters, Resolution,
+};
+use async_graphql::{EmptySubscription, Json, Object, Request, Response, Schema, SimpleObject};
+use async_trait::async_trait;
+use linera_sdk::{
+    base::{ApplicationId, Owner},
+    QueryContext, Service, ViewStateStorage,
+};
+use std::sync::Arc;
+
+#[async_trait]
+impl Service for Counter {
+    type Error = linera_sdk::base::Error;
+    type Storage = ViewStateStorage<Self>;
+
+    async fn handle_query(
+        self: Arc<Self>,
+        _context: &QueryContext,
+        request: Request,
+    ) -> Result<Response, Self::Error> {
+        let schema = Schema::build(QueryRoot(self), MutationRoot, EmptySubscription).finish();
+        Ok(schema.execute(request).await)
+    }
+}
+
+/// A named counter and its value.
+#[derive(Debug, SimpleObject)]
+pub struct KeyedCounter {
+    pub key: String,
+    pub value: u64,
+}
+
+/// The queries on the counter hosted on this chain.
+struct QueryRoot(Arc<Counter>);
+
+#[Object]
+impl QueryRoot {
+    /// The value of the counter on this chain.
+    async fn value(&self) -> u64 {
+        self.0.value
+    }
+
+    /// The total of the changes made on all chains, as far as this chain knows.
+    async fn global_value(&self) -> u64 {
+        self.0.global_value()
+    }
+
+    /// The amount `increment` and `decrement` change the counter by, once a transaction on
+    /// this chain has read the application parameters.
+    async fn step(&self) -> Option<u64> {
+        self.0.parameters.map(|parameters| parameters.step)
+    }
+
+    async fn bounds(&self) -> Json<CounterBounds> {
+        Json(self.0.bounds.clone())
+    }
+
+    async fn access(&self) -> Json<AccessMode> {
+        Json(self.0.access)
+    }
+
+    /// The value of the counter named `key`.
+    async fn key(&self, key: String) -> async_graphql::Result<u64> {
+        Ok(KeyedCounters::load_stored().await?.value(&key).await?)
+    }
+
+    /// The named counters whose key starts with `prefix`, sorted by key.
+    async fn keys_with_prefix(&self, prefix: String) -> async_graphql::Result<Vec<KeyedCounter>> {
+        let counters = KeyedCounters::load_stored().await?;
+        Ok(keyed_counters(counters.with_prefix(&prefix).await?))
+    }
+
+    /// The `n` largest named counters, largest first.
+    async fn top_keys(&self, n: usize) -> async_graphql::Result<Vec<KeyedCounter>> {
+        let counters = KeyedCounters::load_stored().await?;
+        Ok(keyed_counters(counters.top(n).await?))
+    }
+
+    /// The latest changes of the counter value, oldest first.
+    async fn history(&self) -> Json<Vec<HistoryEntry>> {
+        Json(self.0.history.iter().cloned().collect())
+    }
+
+    /// The changes made from `from` until before `to`, in microseconds, aggregated per period
+    /// of `resolution`.
+    async fn aggregates(
+        &self,
+        resolution: Json<Resolution>,
+        from: u64,
+        to: u64,
+    ) -> Json<Vec<Bucket>> {
+        Json(self.0.aggregate(resolution.0, from, to))
+    }
+
+    /// The value the operation `signer` submitted with the idempotency key `key` resulted in,
+    /// if the key is still remembered.
+    async fn idempotent_result(&self, signer: Option<Owner>, key: String) -> Option<u64> {
+        self.0.idempotency.get(&signer, &key).copied()
+    }
+
+    /// The calls made by each caller application.
+    async fn caller_usage(&self) -> Json<Vec<(ApplicationId, CallerUsage)>> {
+        Json(
+            self.0
+                .caller_usage
+                .iter()
+                .map(|(caller, usage)| (*caller, *usage))
+                .collect(),
+        )
+    }
+}
+
+/// The mutations, each returning a serialized [`CounterOperation`] to be submitted. With an
+/// idempotency key, retrying the operation does not apply it twice.
+struct MutationRoot;
+
+#[Object]
+impl MutationRoot {
+    async fn increment(&self, idempotency_key: Option<String>) -> Vec<u8> {
+        operation_bytes(CounterOperation::Increment, idempotency_key)
+    }
+
+    async fn increment_by(&self, value: u64, idempotency_key: Option<String>) -> Vec<u8> {
+        operation_bytes(CounterOperation::IncrementBy { value }, idempotency_key)
+    }
+
+    async fn decrement(&self, idempotency_key: Option<String>) -> Vec<u8> {
+        operation_bytes(CounterOperation::Decrement, idempotency_key)
+    }
+
+    async fn decrement_by(&self, value: u64, idempotency_key: Option<String>) -> Vec<u8> {
+        operation_bytes(CounterOperation::DecrementBy { value }, idempotency_key)
+    }
+
+    async fn reset(&self, idempotency_key: Option<String>) -> Vec<u8> {
+        operation_bytes(CounterOperation::Reset, idempotency_key)
+    }
+
+    async fn increment_key(&self, key: String, idempotency_key: Option<String>) -> Vec<u8> {
+        operation_bytes(CounterOperation::IncrementKey { key }, idempotency_key)
+    }
+
+    async fn increment_key_by(
+        &self,
+        key: String,
+        value: u64,
+        idempotency_key: Option<String>,
+    ) -> Vec<u8> {
+        operation_bytes(
+            CounterOperation::IncrementKeyBy { key, value },
+            idempotency_key,
+        )
+    }
+}
+
+fn keyed_counters(counters: Vec<(String, u64)>) -> Vec<KeyedCounter> {
+    counters
+        .into_iter()
+        .map(|(key, value)| KeyedCounter { key, value })
+        .collect()
+}
+
+fn operation_bytes(operation: CounterOperation, idempotency_key: Option<String>) -> Vec<u8> {
+    let operation = match idempotency_key {
+        Some(key) => CounterOperation::Idempotent {
+            key,
+            operation: Box::new(operation),
+        },
+        None => operation,
+    };
+    bcs::to_bytes(&operation).expect("counter operations are serializable")
+}
+
+impl linera_sdk::service::WitInterface for Counter {
+    const EXPORTS: &'static [&'static str] = &["handle_query"];
+}
@@ -0,0 +1,77 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+
+/// The number of keys remembered per signer by [`IdempotencyWindow::default`].
+pub const DEFAULT_CAPACITY: usize = 64;
+
+/// Remembers the results of the latest requests of each signer by their idempotency key, so
+/// that a retried request is answered with its original result instead of being executed
+/// twice.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IdempotencyWindow<S: Ord, T> {
+    /// The number of keys remembered per signer. Older keys are forgotten.
+    capacity: usize,
+    /// The latest keys of each signer with the results of their requests, oldest first.
+    seen: BTreeMap<S, VecDeque<(String, T)>>,
+}
+
+impl<S: Ord, T> Default for IdempotencyWindow<S, T> {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl<S: Ord, T> IdempotencyWindow<S, T> {
+    /// Creates a window remembering up to `capacity` keys per signer.
+    pub fn new(capacity: usize) -> Self {
+        IdempotencyWindow {
+            capacity,
+            seen: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the result of the request `signer` made with `key`, if it is still remembered.
+    pub fn get(&self, signer: &S, key: &str) -> Option<&T> {
+        self.seen
+            .get(signer)?
+            .iter()
+            .find(|(seen, _)| seen == key)
+            .map(|(_, result)| result)
+    }
+
+    /// Remembers `result` as the result of the request `signer` made with `key`, forgetting
+    /// the oldest key of `signer` if the window is full.
+    pub fn insert(&mut self, signer: S, key: String, result: T) {
+        if self.capacity == 0 {
+            return;
+        }
+        let seen = self.seen.entry(signer).or_default();
+        seen.retain(|(seen, _)| *seen != key);
+        if seen.len() == self.capacity {
+            seen.pop_front();
+        }
+        seen.push_back((key, result));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_forgets_oldest_keys() {
+        let mut window = IdempotencyWindow::new(2);
+        window.insert("alice", "a".to_string(), 1);
+        window.insert("alice", "b".to_string(), 2);
+        window.insert("bob", "a".to_string(), 10);
+        assert_eq!(window.get(&"alice", "a"), Some(&1));
+        assert_eq!(window.get(&"bob", "a"), Some(&10));
+        assert_eq!(window.get(&"bob", "b"), None);
+
+        window.insert("alice", "c".to_string(), 3);
+        assert_eq!(window.get(&"alice", "a"), None);
+        assert_eq!(window.get(&"alice", "b"), Some(&2));
+        assert_eq!(window.get(&"alice", "c"), Some(&3));
+        assert_eq!(window.get(&"bob", "a"), Some(&10));
+    }
+}
@@ -1,43 +1,1702 @@
+use counter_abi::CounterApplicationCall;
 use linera_sdk::{
-    base::{ApplicationId, ChainId, SessionId},
+    base::{ApplicationId, ChainId, Owner, SessionId, Timestamp},
     contract::system_api,
     ApplicationCallResult, CalleeContext, Contract, EffectContext, ExecutionResult,
     MessageContext, OperationContext, Resource, SessionCallResult, ViewStateStorage,
 };
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+mod service;
 
 /// The state of the poll application.
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct Poll {
+    /// Identifies the current poll. Incremented whenever a poll is created, so 0 means that no
+    /// poll was ever created on this chain.
+    pub id: u64,
     /// The poll question.
     pub question: String,
-    /// The poll options and their vote counts.
-    pub options: HashMap<String, u64>,
-    /// Total number of votes cast.
+    /// The poll options and their vote counts, in creation order.
+    pub options: Vec<PollOption>,
+    /// Total number of voters.
     pub total_votes: u64,
+    /// Total weight of all ballots. Equal to `total_votes` unless the poll is token-weighted.
+    pub total_weight: u64,
     /// Whether the poll is still active.
     pub is_active: bool,
+    /// How ballots are cast and counted.
+    pub mode: VotingMode,
+    /// The ballot cast by each voter.
+    pub voters: HashMap<Owner, VoterRecord>,
+    /// Token balances taken when a [`Weighting::Snapshot`] poll was created.
+    pub snapshot: HashMap<Owner, u64>,
+    /// The settings the poll was created with.
+    pub settings: PollSettings,
+    /// The ballot commitments of a secret poll, by voter.
+    pub commitments: HashMap<Owner, [u8; 32]>,
+    /// The outcome computed when the poll was closed, updated as secret ballots are revealed.
+    pub outcome: Option<Outcome>,
+    /// The owner who created the poll, and who manages its allow-list.
+    pub creator: Option<Owner>,
+    /// The value returned by the poll's [`ProposalAction`], once it was executed.
+    pub action_result: Option<Vec<u8>>,
+    /// Whether the creator cancelled the poll's [`ProposalAction`] instead of executing it.
+    pub action_cancelled: bool,
+    /// When the revealing period of a closed secret poll ends. Ballots not revealed by then
+    /// count as abstentions.
+    pub reveal_deadline: Option<Timestamp>,
+    /// Every counted ballot, in the order it was counted.
+    pub log: Vec<VoteLogEntry>,
+    /// The delegation made by each delegating voter.
+    pub delegations: HashMap<Owner, Delegation>,
+    /// The weight delegated to each owner, directly or through other delegators, by
+    /// delegators who did not vote.
+    pub delegated: HashMap<Owner, u64>,
+    /// The survey hosted on this chain, alongside the poll.
+    pub survey: Survey,
+    /// The round of the current poll. Starts at 1, and a new round starts whenever the poll is
+    /// reopened with [`ReopenPolicy::NewRound`].
+    pub round: u64,
+    /// The results recorded when each round closed, of every poll created on this chain.
+    pub history: Vec<PollResults>,
+    /// The current rate-limiting window of each chain ballots were cast from.
+    pub rate_windows: HashMap<ChainId, RateWindow>,
+    /// The number of participants of the current poll, as last returned by its participant
+    /// counter.
+    pub participants: Option<u64>,
+}
+
+/// How long secret ballots can be revealed after a poll closes, in microseconds, unless its
+/// settings say otherwise: one day.
+pub const DEFAULT_REVEAL_PERIOD_MICROS: u64 = 24 * 60 * 60 * 1_000_000;
+
+/// The smallest number of options a poll can have.
+pub const MIN_OPTIONS: usize = 2;
+
+/// The largest number of options a poll can have.
+pub const MAX_OPTIONS: usize = 64;
+
+/// An option of a poll and the votes it received.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PollOption {
+    /// The name of the option, unique within the poll.
+    pub name: String,
+    /// The votes, approvals or weight the option received, depending on the voting mode.
+    pub votes: u64,
+    /// The description, image and link shown with the option.
+    pub metadata: OptionMetadata,
+}
+
+/// Optional information shown with a poll option.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OptionMetadata {
+    /// A longer description of the option.
+    pub description: Option<String>,
+    /// The URL of an image illustrating the option.
+    pub image_url: Option<String>,
+    /// A link to more information about the option.
+    pub link: Option<String>,
+}
+
+/// An option of a poll being created.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NewOption {
+    /// The name of the option, unique within the poll.
+    pub name: String,
+    /// The description, image and link shown with the option.
+    pub metadata: OptionMetadata,
+}
+
+impl From<String> for NewOption {
+    fn from(name: String) -> Self {
+        NewOption {
+            name,
+            metadata: OptionMetadata::default(),
+        }
+    }
+}
+
+impl From<NewOption> for PollOption {
+    fn from(option: NewOption) -> Self {
+        PollOption {
+            name: option.name,
+            votes: 0,
+            metadata: option.metadata,
+        }
+    }
+}
+
+impl From<&str> for NewOption {
+    fn from(name: &str) -> Self {
+        NewOption::from(name.to_string())
+    }
+}
+
+/// A group of questions that respondents answer together, each with its own options and
+/// voting mode.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Survey {
+    /// Identifies the current survey. Incremented whenever a survey is created, so 0 means that
+    /// no survey was ever created on this chain.
+    pub id: u64,
+    /// The title of the survey.
+    pub title: String,
+    /// The questions and their counts, in creation order.
+    pub questions: Vec<SurveyQuestion>,
+    /// The votes each respondent's answers gave to the options of each question, by position.
+    pub responses: HashMap<Owner, Vec<Vec<(usize, u64)>>>,
+    /// Whether the survey still accepts responses.
+    pub is_active: bool,
+    /// The owner who created the survey, and who can close or replace it while it is active.
+    pub creator: Option<Owner>,
+    /// Who may respond, and the limits of the responses.
+    pub settings: SurveySettings,
+    /// The current rate-limiting window of each chain responses were sent from.
+    pub rate_windows: HashMap<ChainId, RateWindow>,
+}
+
+/// A question of a survey and the votes its options received.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SurveyQuestion {
+    /// The question.
+    pub question: String,
+    /// The options and their vote counts, in creation order.
+    pub options: Vec<PollOption>,
+    /// How answers to the question are cast and counted. Surveys are not token-weighted.
+    pub mode: VotingMode,
+}
+
+/// A question of a survey being created.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NewQuestion {
+    /// The question.
+    pub question: String,
+    /// The options of the question.
+    pub options: Vec<NewOption>,
+    /// How answers to the question are cast and counted.
+    pub mode: VotingMode,
+}
+
+/// The settings a survey is created with, independent of those of the poll it is hosted
+/// alongside.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SurveySettings {
+    /// Who may respond. Unlike a poll's, an allow-list cannot be changed after the survey is
+    /// created.
+    pub eligibility: Eligibility,
+    /// The anti-spam limits of the responses. `max_options` applies to each question, and the
+    /// vote fee is paid to the survey creator.
+    pub limits: PollLimits,
+}
+
+/// Built-in poll layouts, to create common polls with a single operation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PollTemplate {
+    /// A plurality poll between "Yes", "No" and "Abstain".
+    YesNoAbstain,
+    /// A plurality poll rating from "1" to "5".
+    Rating,
+    /// An approval poll between the given options, any number of which can be chosen.
+    MultipleChoice { options: Vec<NewOption> },
+}
+
+impl PollTemplate {
+    /// Returns the options and voting mode of polls created from this template.
+    pub fn into_parts(self) -> (Vec<NewOption>, VotingMode) {
+        match self {
+            PollTemplate::YesNoAbstain => (
+                vec!["Yes".into(), "No".into(), "Abstain".into()],
+                VotingMode::Plurality,
+            ),
+            PollTemplate::Rating => (
+                (1..=5).map(|rating| rating.to_string().into()).collect(),
+                VotingMode::Plurality,
+            ),
+            PollTemplate::MultipleChoice { options } => (options, VotingMode::Approval),
+        }
+    }
+}
+
+impl PollOption {
+    /// Creates an option without votes.
+    pub fn new(name: impl Into<String>) -> Self {
+        PollOption {
+            name: name.into(),
+            votes: 0,
+            metadata: OptionMetadata::default(),
+        }
+    }
+}
+
+/// Refers to an option of a poll, by its position or by its name.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OptionRef {
+    /// The position of the option, starting at 0.
+    Index(usize),
+    /// The name of the option.
+    Name(String),
+}
+
+impl fmt::Display for OptionRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OptionRef::Index(index) => write!(f, "#{index}"),
+            OptionRef::Name(name) => write!(f, "{name:?}"),
+        }
+    }
+}
+
+/// How ballots are cast and counted in a poll.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VotingMode {
+    /// Each voter picks exactly one option.
+    #[default]
+    Plurality,
+    /// Each voter approves any subset of the options.
+    Approval,
+    /// Each voter spends up to `credits`, where `n` votes for one option cost `n²` credits.
+    Quadratic { credits: u64 },
+    /// Each voter picks one option, weighted by their balance in the fungible-token
+    /// application `token`.
+    TokenWeighted {
+        token: ApplicationId,
+        weighting: Weighting,
+    },
+}
+
+/// When the balances of a [`VotingMode::TokenWeighted`] poll are read.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Weighting {
+    /// The voter's balance is read when they vote.
+    AtVote,
+    /// The balances of `holders` are read when the poll is created. Other owners cannot vote.
+    Snapshot { holders: Vec<Owner> },
+}
+
+/// Optional behaviour of a poll, chosen at creation.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PollSettings {
+    /// Whether ballots are committed while the poll is active and revealed after it closes.
+    /// Only plurality and token-weighted polls can be secret.
+    pub secret_ballot: bool,
+    /// The participation needed for the poll to produce a result.
+    pub quorum: Option<Quorum>,
+    /// The share the leading option needs to pass. Without one, any unique leader passes.
+    pub pass_threshold: Option<Ratio>,
+    /// Who may vote.
+    pub eligibility: Eligibility,
+    /// The application call to execute if the poll passes.
+    pub action: Option<ProposalAction>,
+    /// The description, tags and category shown with the poll.
+    pub metadata: PollMetadata,
+    /// What reopening the poll after it closed does.
+    pub reopen: ReopenPolicy,
+    /// The anti-spam limits of the poll.
+    pub limits: PollLimits,
+    /// The counter application that counts the participants of the poll, incremented for
+    /// every ballot cast or committed. The poll needs a caller grant with write permission.
+    pub participant_counter: Option<ApplicationId>,
+    /// How long secret ballots can be revealed after the poll closes, in microseconds.
+    /// Defaults to [`DEFAULT_REVEAL_PERIOD_MICROS`].
+    pub reveal_period_micros: Option<u64>,
+}
+
+/// Optional anti-spam limits of a poll, enforced on every path ballots are cast by.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PollLimits {
+    /// The number of ballots each chain can cast per time window.
+    pub rate_limit: Option<RateLimit>,
+    /// The fee each voter pays to the poll creator when casting or committing a ballot.
+    pub vote_fee: Option<VoteFee>,
+    /// The largest number of options the poll can have, if lower than [`MAX_OPTIONS`].
+    pub max_options: Option<usize>,
+    /// The largest number of voters the poll accepts.
+    pub max_voters: Option<u64>,
+}
+
+/// A limit on the ballots cast from each chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateLimit {
+    /// The number of ballots each chain can cast within a window.
+    pub votes: u64,
+    /// The length of a window, in microseconds.
+    pub window_micros: u64,
+}
+
+/// A fee paid in a fungible token for each ballot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VoteFee {
+    /// The fungible token application the fee is paid in.
+    pub token: ApplicationId,
+    /// The amount paid per ballot.
+    pub amount: u64,
+}
+
+/// The ballots a chain cast within the current rate-limiting window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateWindow {
+    /// When the window started.
+    pub start: Timestamp,
+    /// The ballots cast since then.
+    pub votes: u64,
+}
+
+/// What reopening a closed poll does.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReopenPolicy {
+    /// The results are final once the poll is closed, and it cannot be reopened.
+    #[default]
+    Forbidden,
+    /// Reopening starts a new round with no votes, keeping the results of past rounds in
+    /// [`Poll::history`].
+    NewRound,
+}
+
+/// Optional information shown with a poll.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PollMetadata {
+    /// A longer description of the poll.
+    pub description: Option<String>,
+    /// Free-form tags to search polls by.
+    pub tags: Vec<String>,
+    /// The category the poll belongs to.
+    pub category: Option<String>,
+}
+
+/// An application call attached to a poll. Once the poll is closed with a
+/// [`Outcome::Passed`] outcome, anyone can execute it, exactly once.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProposalAction {
+    /// The application to call.
+    pub application_id: ApplicationId,
+    /// The serialized application call.
+    pub call: Vec<u8>,
+}
+
+/// Who may vote in a poll.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Eligibility {
+    /// Everyone.
+    #[default]
+    Open,
+    /// The listed owners, and any owner voting from one of the listed chains. The creator can
+    /// change the list while the poll is active.
+    AllowList {
+        owners: Vec<Owner>,
+        chains: Vec<ChainId>,
+    },
+    /// Owners holding at least `minimum` tokens of the fungible-token application `token`.
+    TokenHolders { token: ApplicationId, minimum: u64 },
+}
+
+impl Eligibility {
+    /// Returns whether `voter`, voting from `chain`, is eligible. `token_balance` is the
+    /// voter's balance when voting is restricted to [`Eligibility::TokenHolders`].
+    pub fn admits(&self, voter: &Owner, chain: &ChainId, token_balance: Option<u64>) -> bool {
+        match self {
+            Eligibility::Open => true,
+            Eligibility::AllowList { owners, chains } => {
+                owners.contains(voter) || chains.contains(chain)
+            }
+            Eligibility::TokenHolders { minimum, .. } => {
+                token_balance.is_some_and(|balance| balance >= *minimum)
+            }
+        }
+    }
+}
+
+/// An entry of an [`Eligibility::AllowList`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EligibleVoter {
+    /// A single owner.
+    Owner(Owner),
+    /// Every owner voting from this chain.
+    Chain(ChainId),
+}
+
+/// The minimum participation in a poll.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Quorum {
+    /// At least this many voters.
+    Voters(u64),
+    /// At least this much total weight.
+    Weight(u64),
+}
+
+/// The fraction `numerator / denominator`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Ratio {
+    pub numerator: u64,
+    pub denominator: u64,
+}
+
+impl Ratio {
+    /// Returns whether `part` is at least this fraction of `whole`.
+    pub fn is_reached(&self, part: u64, whole: u64) -> bool {
+        u128::from(part) * u128::from(self.denominator)
+            >= u128::from(whole) * u128::from(self.numerator)
+    }
+}
+
+/// The outcome of a closed poll.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Outcome {
+    /// The option at this position leads and reached the pass threshold.
+    Passed { option: usize },
+    /// The leading option did not reach the pass threshold.
+    Rejected,
+    /// Too few voters or too little weight took part.
+    QuorumNotMet,
+    /// Several options share the lead.
+    Tie,
+}
+
+/// The calls the poll application makes to a fungible-token application.
+#[derive(Debug, Deserialize, Serialize)]
+pub enum FungibleTokenCall {
+    /// Gets the balance of `owner`.
+    Balance { owner: Owner },
+    /// Transfers `amount` tokens from `owner`, who must have authenticated the call, to
+    /// `target`.
+    Transfer {
+        owner: Owner,
+        amount: u64,
+        target: Owner,
+    },
+}
+
+/// A ballot. Its shape must match the [`VotingMode`] of the poll.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Ballot {
+    /// A single option, for plurality and token-weighted polls.
+    Single { option: OptionRef },
+    /// The approved options, for approval polls.
+    Approval { options: Vec<OptionRef> },
+    /// The number of votes given to each option, for quadratic polls.
+    Quadratic { votes: Vec<(OptionRef, u64)> },
+}
+
+/// A voter's delegation of their vote to another owner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Delegation {
+    /// The owner the vote is delegated to.
+    pub to: Owner,
+    /// The weight of the delegator when delegating.
+    pub weight: u64,
+}
+
+/// The ballot a voter has cast.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VoterRecord {
+    /// The ballot as submitted.
+    pub ballot: Ballot,
+    /// The weight of the ballot. Always 1 unless the poll is token-weighted.
+    pub weight: u64,
+    /// Credits spent by the ballot. Always 0 outside quadratic polls.
+    pub credits_spent: u64,
+}
+
+/// Where and when a ballot was cast.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VoteOrigin {
+    /// The chain the ballot was cast from.
+    pub chain_id: ChainId,
+    /// The time the ballot was counted.
+    pub timestamp: Timestamp,
+}
+
+/// An entry of the vote log, recorded for each counted ballot.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VoteLogEntry {
+    /// The voter.
+    pub voter: Owner,
+    /// Where and when the ballot was cast.
+    pub origin: VoteOrigin,
+    /// The votes the ballot added to each option, by position, before weighting.
+    pub votes: Vec<(usize, u64)>,
+    /// The weight of the ballot.
+    pub weight: u64,
+}
+
+/// The result of recomputing the tallies of a poll from its vote log.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TallyAudit {
+    /// The tally of each option recomputed from the log, in creation order.
+    pub tallies: Vec<u64>,
+    /// The number of voters in the log.
+    pub total_votes: u64,
+    /// The total weight of the ballots in the log.
+    pub total_weight: u64,
+    /// Whether the recomputed tallies and totals match the stored ones.
+    pub matches: bool,
+}
+
+/// The tallies of a poll, as returned by [`PollApplicationCall::GetResults`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PollResults {
+    /// The poll the tallies belong to.
+    pub poll_id: u64,
+    /// The round of the poll the tallies belong to.
+    pub round: u64,
+    /// The poll question.
+    pub question: String,
+    /// The voting mode the tallies were produced by.
+    pub mode: VotingMode,
+    /// Votes per option for plurality and quadratic polls, approvals per option for approval
+    /// polls, and weight per option for token-weighted polls, in creation order.
+    pub tallies: Vec<PollOption>,
+    /// Total number of voters.
+    pub total_votes: u64,
+    /// Total weight of all ballots.
+    pub total_weight: u64,
+    /// Total credits spent by all voters. Always 0 outside quadratic polls.
+    pub credits_spent: u64,
+    /// Voters of a secret poll who committed a ballot but have not revealed it.
+    pub unrevealed: Vec<Owner>,
+    /// The outcome of the poll, once it is closed.
+    pub outcome: Option<Outcome>,
+    /// The value returned by the poll's action, once it was executed.
+    pub action_result: Option<Vec<u8>>,
+    /// Whether the creator cancelled the poll's action instead of executing it.
+    pub action_cancelled: bool,
+}
+
+/// The errors that can occur when creating a poll or voting.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PollError {
+    /// The poll has fewer than [`MIN_OPTIONS`] or more than [`MAX_OPTIONS`] options.
+    InvalidOptionCount(usize),
+    /// An option name is empty or only whitespace.
+    EmptyOptionName,
+    /// The pass threshold has a zero denominator or is greater than 1.
+    InvalidThreshold,
+    /// The vote was not signed, so the voter is unknown.
+    MissingSigner,
+    /// The vote is for a poll that does not exist on this chain.
+    UnknownPoll(u64),
+    /// The poll is closed.
+    PollClosed,
+    /// The poll is still active, so ballots cannot be revealed yet.
+    PollStillActive,
+    /// The voter has already cast a ballot.
+    AlreadyVoted,
+    /// The voter is not eligible to vote in this poll.
+    NotEligible,
+    /// Only the creator of the poll or survey can do this.
+    NotCreator,
+    /// The poll is not restricted to an allow-list.
+    NoAllowList,
+    /// The poll has no action to execute.
+    NoAction,
+    /// The poll did not pass, so its action cannot be executed.
+    NotPassed,
+    /// The action of the poll was already executed.
+    AlreadyExecuted,
+    /// The poll is closed for good, as its results are final or its action was executed.
+    PollFinalized,
+    /// The limits of the poll are inconsistent.
+    InvalidLimits,
+    /// The poll charges a vote fee but has no creator to receive it.
+    NoFeeRecipient,
+    /// The chain cast too many ballots in the current window.
+    RateLimited,
+    /// The poll has reached its maximum number of voters.
+    TooManyVoters,
+    /// The ballot shape does not match the voting mode.
+    BallotMismatch,
+    /// The ballot does not select any option.
+    EmptyBallot,
+    /// The ballot refers to an option that is not part of the poll.
+    UnknownOption(OptionRef),
+    /// The poll or the ballot lists the same option more than once.
+    DuplicateOption(String),
+    /// The quadratic ballot costs more than the voter's credit budget.
+    InsufficientCredits { cost: u64, budget: u64 },
+    /// The poll has an action but no creator to close it.
+    ActionWithoutCreator,
+    /// The action of the previous poll passed but was not executed yet.
+    ActionPending,
+    /// Some secret ballots are not revealed yet and the revealing period is not over, so the
+    /// outcome may still change.
+    UnrevealedBallots,
+    /// The revealing period of the secret poll is over.
+    RevealClosed,
+    /// The creator cancelled the action of the poll.
+    ActionCancelled,
+    /// The voter holds no tokens, or was not part of the snapshot.
+    NoVotingWeight,
+    /// The weighted votes of the poll exceed what a tally can hold.
+    WeightOverflow,
+    /// The delegation would create a cycle, or delegates to the voter themselves.
+    DelegationCycle,
+    /// Secret ballots are only supported by plurality and token-weighted polls.
+    SecretBallotUnsupported,
+    /// The poll is secret, so ballots must be committed and revealed.
+    SecretBallot,
+    /// The poll is not secret, so ballots cannot be committed.
+    NotSecretBallot,
+    /// The voter has not committed a ballot.
+    NoCommitment,
+    /// The revealed ballot does not match the commitment.
+    CommitmentMismatch,
+    /// A survey needs at least one question.
+    EmptySurvey,
+    /// Survey questions cannot be token-weighted.
+    SurveyModeUnsupported,
+    /// The survey is closed, or was never created.
+    SurveyClosed,
+    /// A survey response must answer each question exactly once.
+    AnswerCountMismatch { expected: usize, actual: usize },
+    /// The survey has no question at this position.
+    UnknownQuestion(usize),
+}
+
+impl fmt::Display for PollError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PollError::InvalidOptionCount(count) => write!(
+                f,
+                "a poll needs between {MIN_OPTIONS} and {MAX_OPTIONS} options, not {count}"
+            ),
+            PollError::EmptyOptionName => write!(f, "option names cannot be empty"),
+            PollError::InvalidThreshold => write!(f, "the pass threshold must be at most 1"),
+            PollError::MissingSigner => write!(f, "votes must be signed"),
+            PollError::UnknownPoll(poll_id) => write!(f, "unknown poll {poll_id}"),
+            PollError::PollClosed => write!(f, "the poll is closed"),
+            PollError::PollStillActive => write!(f, "the poll is still active"),
+            PollError::AlreadyVoted => write!(f, "the voter has already voted"),
+            PollError::NotEligible => write!(f, "the voter is not eligible"),
+            PollError::NotCreator => write!(f, "only the creator can do this"),
+            PollError::NoAllowList => write!(f, "the poll has no allow-list"),
+            PollError::NoAction => write!(f, "the poll has no action"),
+            PollError::NotPassed => write!(f, "the poll did not pass"),
+            PollError::AlreadyExecuted => write!(f, "the action was already executed"),
+            PollError::PollFinalized => write!(f, "the poll is finalized"),
+            PollError::InvalidLimits => write!(f, "the poll limits are inconsistent"),
+            PollError::NoFeeRecipient => write!(f, "a vote fee needs a poll creator"),
+            PollError::RateLimited => write!(f, "too many ballots from this chain, try later"),
+            PollError::TooManyVoters => write!(f, "the poll has reached its maximum of voters"),
+            PollError::BallotMismatch => write!(f, "the ballot does not match the voting mode"),
+            PollError::EmptyBallot => write!(f, "the ballot does not select any option"),
+            PollError::UnknownOption(option) => write!(f, "unknown option {option}"),
+            PollError::DuplicateOption(option) => write!(f, "option {option:?} is listed twice"),
+            PollError::InsufficientCredits { cost, budget } => {
+                write!(
+                    f,
+                    "the ballot costs {cost} credits but the budget is {budget}"
+                )
+            }
+            PollError::ActionWithoutCreator => write!(f, "a poll with an action needs a creator"),
+            PollError::ActionPending => write!(f, "the action of the poll is still pending"),
+            PollError::UnrevealedBallots => write!(f, "some secret ballots are not revealed yet"),
+            PollError::RevealClosed => write!(f, "the revealing period is over"),
+            PollError::ActionCancelled => write!(f, "the action of the poll was cancelled"),
+            PollError::NoVotingWeight => write!(f, "the voter has no voting weight"),
+            PollError::WeightOverflow => write!(f, "the weighted votes overflow the tally"),
+            PollError::DelegationCycle => write!(f, "the delegation would create a cycle"),
+            PollError::SecretBallotUnsupported => {
+                write!(f, "secret ballots require a single-choice voting mode")
+            }
+            PollError::SecretBallot => write!(f, "the poll is secret, commit the ballot instead"),
+            PollError::NotSecretBallot => write!(f, "the poll is not secret, vote directly"),
+            PollError::NoCommitment => write!(f, "the voter has not committed a ballot"),
+            PollError::CommitmentMismatch => {
+                write!(f, "the revealed ballot does not match the commitment")
+            }
+            PollError::EmptySurvey => write!(f, "a survey needs at least one question"),
+            PollError::SurveyModeUnsupported => {
+                write!(f, "survey questions cannot be token-weighted")
+            }
+            PollError::SurveyClosed => write!(f, "the survey is closed"),
+            PollError::AnswerCountMismatch { expected, actual } => {
+                write!(f, "expected {expected} answers, got {actual}")
+            }
+            PollError::UnknownQuestion(question) => {
+                write!(f, "the survey has no question #{question}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for PollError {}
+
+impl From<PollError> for linera_sdk::base::Error {
+    fn from(_error: PollError) -> Self {
+        linera_sdk::base::Error::default()
+    }
+}
+
+/// Computes the commitment to a secret ballot for the option named `option`, blinded by `salt`.
+///
+/// The voter is part of the hash, so that copying someone else's commitment is useless.
+pub fn commitment_hash(voter: &Owner, option: &str, salt: &str) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    for part in [voter.to_string().as_str(), option, salt] {
+        hasher.update((part.len() as u64).to_le_bytes());
+        hasher.update(part.as_bytes());
+    }
+    hasher.finalize().into()
+}
+
+impl Poll {
+    /// Replaces the current poll with a new one, closing it first if it is still active. The
+    /// ballots of the replaced poll are discarded; its outcome stays in the round history.
+    /// A poll whose action is still pending cannot be replaced.
+    ///
+    /// `snapshot` holds the token balances of a [`Weighting::Snapshot`] poll, and `now` is the
+    /// current time.
+    pub fn create(
+        &mut self,
+        creator: Option<Owner>,
+        question: String,
+        options: Vec<NewOption>,
+        mode: VotingMode,
+        settings: PollSettings,
+        snapshot: HashMap<Owner, u64>,
+        now: Timestamp,
+    ) -> Result<(), PollError> {
+        if settings.secret_ballot
+            && !matches!(
+                mode,
+                VotingMode::Plurality | VotingMode::TokenWeighted { .. }
+            )
+        {
+            return Err(PollError::SecretBallotUnsupported);
+        }
+        validate_options(&options)?;
+        if let Some(threshold) = &settings.pass_threshold {
+            if threshold.denominator == 0 || threshold.numerator > threshold.denominator {
+                return Err(PollError::InvalidThreshold);
+            }
+        }
+        let limits = &settings.limits;
+        if let Some(max_options) = limits.max_options {
+            if options.len() > max_options {
+                return Err(PollError::InvalidOptionCount(options.len()));
+            }
+        }
+        validate_limits(limits, creator)?;
+        if settings.action.is_some() && creator.is_none() {
+            return Err(PollError::ActionWithoutCreator);
+        }
+        if self.is_active {
+            // The replaced poll is closed first, so that its results stay in the history.
+            self.finish(now);
+        }
+        if self.action_pending(now) {
+            return Err(PollError::ActionPending);
+        }
+        self.id += 1;
+        self.question = question;
+        self.options = options.into_iter().map(PollOption::from).collect();
+        self.is_active = true;
+        self.mode = mode;
+        self.snapshot = snapshot;
+        self.settings = settings;
+        self.creator = creator;
+        self.round = 1;
+        self.rate_windows.clear();
+        self.participants = None;
+        self.clear_votes();
+        Ok(())
+    }
+
+    /// Discards the ballots, counts and outcome of the current round.
+    fn clear_votes(&mut self) {
+        for option in &mut self.options {
+            option.votes = 0;
+        }
+        self.total_votes = 0;
+        self.total_weight = 0;
+        self.voters.clear();
+        self.commitments.clear();
+        self.outcome = None;
+        self.action_result = None;
+        self.action_cancelled = false;
+        self.reveal_deadline = None;
+        self.log.clear();
+        self.delegations.clear();
+        self.delegated.clear();
+    }
+
+    /// Returns whether `voter`, voting from `chain`, may vote. `token_balance` is the voter's
+    /// balance when the poll is restricted to [`Eligibility::TokenHolders`].
+    pub fn is_eligible(&self, voter: &Owner, chain: &ChainId, token_balance: Option<u64>) -> bool {
+        self.settings
+            .eligibility
+            .admits(voter, chain, token_balance)
+    }
+
+    /// Adds `voter` to the allow-list, on behalf of `signer`.
+    pub fn add_eligible(
+        &mut self,
+        signer: Option<Owner>,
+        voter: EligibleVoter,
+    ) -> Result<(), PollError> {
+        let (owners, chains) = self.allow_list(signer)?;
+        match voter {
+            EligibleVoter::Owner(owner) if !owners.contains(&owner) => owners.push(owner),
+            EligibleVoter::Chain(chain) if !chains.contains(&chain) => chains.push(chain),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Removes `voter` from the allow-list, on behalf of `signer`. Ballots already cast are
+    /// still counted.
+    pub fn remove_eligible(
+        &mut self,
+        signer: Option<Owner>,
+        voter: EligibleVoter,
+    ) -> Result<(), PollError> {
+        let (owners, chains) = self.allow_list(signer)?;
+        match voter {
+            EligibleVoter::Owner(owner) => owners.retain(|listed| *listed != owner),
+            EligibleVoter::Chain(chain) => chains.retain(|listed| *listed != chain),
+        }
+        Ok(())
+    }
+
+    /// Returns the allow-list, if `signer` may change it.
+    fn allow_list(
+        &mut self,
+        signer: Option<Owner>,
+    ) -> Result<(&mut Vec<Owner>, &mut Vec<ChainId>), PollError> {
+        if !self.is_active {
+            return Err(PollError::PollClosed);
+        }
+        if signer.is_none() || signer != self.creator {
+            return Err(PollError::NotCreator);
+        }
+        match &mut self.settings.eligibility {
+            Eligibility::AllowList { owners, chains } => Ok((owners, chains)),
+            _ => Err(PollError::NoAllowList),
+        }
+    }
+
+    /// Checks that `signer` may close or reopen the poll. Only its creator can, if it has one.
+    fn check_creator(&self, signer: Option<Owner>) -> Result<(), PollError> {
+        match self.creator {
+            Some(creator) if signer != Some(creator) => Err(PollError::NotCreator),
+            _ => Ok(()),
+        }
+    }
+
+    /// Closes the poll at time `now` on behalf of `signer` and records its outcome. The ballots
+    /// of a secret poll can then be revealed until the end of its revealing period.
+    pub fn close(&mut self, signer: Option<Owner>, now: Timestamp) -> Result<Outcome, PollError> {
+        self.check_creator(signer)?;
+        if !self.is_active {
+            return Err(PollError::PollClosed);
+        }
+        Ok(self.finish(now))
+    }
+
+    /// Closes the poll at time `now`, records its outcome and returns it.
+    fn finish(&mut self, now: Timestamp) -> Outcome {
+        self.is_active = false;
+        if self.settings.secret_ballot {
+            let period = self
+                .settings
+                .reveal_period_micros
+                .unwrap_or(DEFAULT_REVEAL_PERIOD_MICROS);
+            self.reveal_deadline = Some(Timestamp::from(now.micros().saturating_add(period)));
+        }
+        let outcome = self.tally();
+        self.outcome = Some(outcome.clone());
+        self.record_round();
+        outcome
+    }
+
+    /// Reopens a closed poll for a new round at time `now` on behalf of `signer`, if its
+    /// [`ReopenPolicy`] allows it. A poll whose action is still pending cannot be reopened, as
+    /// the new round would discard the outcome the action depends on.
+    pub fn reopen(&mut self, signer: Option<Owner>, now: Timestamp) -> Result<(), PollError> {
+        self.check_creator(signer)?;
+        if self.is_active {
+            return Err(PollError::PollStillActive);
+        }
+        if self.action_pending(now) {
+            return Err(PollError::ActionPending);
+        }
+        if self.settings.reopen == ReopenPolicy::Forbidden
+            || self.action_result.is_some()
+            || self.action_cancelled
+        {
+            return Err(PollError::PollFinalized);
+        }
+        self.round += 1;
+        self.clear_votes();
+        self.is_active = true;
+        Ok(())
+    }
+
+    /// Records the results of the current round in the history, replacing those recorded
+    /// earlier in the same round.
+    fn record_round(&mut self) {
+        let results = self.results();
+        match self.history.last_mut() {
+            Some(last) if last.poll_id == results.poll_id && last.round == results.round => {
+                *last = results;
+            }
+            _ => self.history.push(results),
+        }
+    }
+
+    /// Returns the results recorded when round `round` of poll `poll_id` closed.
+    pub fn round_results(&self, poll_id: u64, round: u64) -> Option<&PollResults> {
+        self.history
+            .iter()
+            .find(|results| results.poll_id == poll_id && results.round == round)
+    }
+
+    /// Returns the action to execute at time `now`, marking it as executed. The poll must be
+    /// closed, be done revealing secret ballots, and have passed.
+    pub fn take_action(&mut self, now: Timestamp) -> Result<ProposalAction, PollError> {
+        let action = self.settings.action.clone().ok_or(PollError::NoAction)?;
+        if self.is_active {
+            return Err(PollError::PollStillActive);
+        }
+        if self.awaiting_reveals(now) {
+            return Err(PollError::UnrevealedBallots);
+        }
+        if !matches!(self.outcome, Some(Outcome::Passed { .. })) {
+            return Err(PollError::NotPassed);
+        }
+        if self.action_cancelled {
+            return Err(PollError::ActionCancelled);
+        }
+        if self.action_result.is_some() {
+            return Err(PollError::AlreadyExecuted);
+        }
+        // Marking the action before it runs also rejects re-entrant executions.
+        self.action_result = Some(Vec::new());
+        Ok(action)
+    }
+
+    /// Cancels the action of the closed poll on behalf of `signer`, who must be its creator,
+    /// so that it is never executed. The cancellation is recorded in the history.
+    pub fn cancel_action(&mut self, signer: Option<Owner>) -> Result<(), PollError> {
+        self.check_creator(signer)?;
+        if self.settings.action.is_none() {
+            return Err(PollError::NoAction);
+        }
+        if self.is_active {
+            return Err(PollError::PollStillActive);
+        }
+        if self.action_result.is_some() {
+            return Err(PollError::AlreadyExecuted);
+        }
+        if self.action_cancelled {
+            return Err(PollError::ActionCancelled);
+        }
+        self.action_cancelled = true;
+        self.record_round();
+        Ok(())
+    }
+
+    /// Returns whether, at time `now`, secret ballots may still be revealed and change the
+    /// outcome.
+    fn awaiting_reveals(&self, now: Timestamp) -> bool {
+        !self.unrevealed().is_empty() && !self.reveal_deadline.is_some_and(|end| now >= end)
+    }
+
+    /// Returns whether, at time `now`, the poll has an action that may still be executed: the
+    /// poll passed, or revealing the remaining secret ballots may make it pass.
+    fn action_pending(&self, now: Timestamp) -> bool {
+        self.settings.action.is_some()
+            && self.action_result.is_none()
+            && !self.action_cancelled
+            && (matches!(self.outcome, Some(Outcome::Passed { .. })) || self.awaiting_reveals(now))
+    }
+
+    /// Computes the outcome of the poll from the current counts.
+    pub fn tally(&self) -> Outcome {
+        let quorum_met = match self.settings.quorum {
+            None => true,
+            Some(Quorum::Voters(voters)) => self.total_votes >= voters,
+            Some(Quorum::Weight(weight)) => self.total_weight >= weight,
+        };
+        if !quorum_met {
+            return Outcome::QuorumNotMet;
+        }
+        let top = self.options.iter().map(|option| option.votes).max();
+        let mut leaders = self
+            .options
+            .iter()
+            .enumerate()
+            .filter(|(_, option)| Some(option.votes) == top);
+        let (Some((option, leader)), None) = (leaders.next(), leaders.next()) else {
+            return Outcome::Tie;
+        };
+        if let Some(threshold) = &self.settings.pass_threshold {
+            // Quadratic tallies count votes rather than voters, so they are compared with
+            // each other. Otherwise an option's share is relative to everyone who voted.
+            let whole = match self.mode {
+                VotingMode::Quadratic { .. } => {
+                    self.options.iter().map(|option| option.votes).sum()
+                }
+                _ => self.total_weight,
+            };
+            if !threshold.is_reached(leader.votes, whole) {
+                return Outcome::Rejected;
+            }
+        }
+        Outcome::Passed { option }
+    }
+
+    /// Records the ballot of `voter`, who may vote only once, with the given `weight`.
+    pub fn vote(
+        &mut self,
+        voter: Owner,
+        origin: VoteOrigin,
+        ballot: Ballot,
+        weight: u64,
+    ) -> Result<(), PollError> {
+        if !self.is_active {
+            return Err(PollError::PollClosed);
+        }
+        if self.settings.secret_ballot {
+            return Err(PollError::SecretBallot);
+        }
+        self.check_voter_count(self.voters.len())?;
+        self.record(voter, origin, ballot, weight)
+    }
+
+    /// Delegates the vote of `voter`, with the given `weight`, to `to`. Delegations are
+    /// transitive, and a direct vote by `voter` overrides theirs.
+    pub fn delegate(&mut self, voter: Owner, to: Owner, weight: u64) -> Result<(), PollError> {
+        if !self.is_active {
+            return Err(PollError::PollClosed);
+        }
+        if self.has_voted(&voter) {
+            return Err(PollError::AlreadyVoted);
+        }
+        if weight == 0 {
+            return Err(PollError::NoVotingWeight);
+        }
+        let mut delegate = to;
+        loop {
+            if delegate == voter {
+                return Err(PollError::DelegationCycle);
+            }
+            match self.delegations.get(&delegate) {
+                Some(delegation) => delegate = delegation.to,
+                None => break,
+            }
+        }
+        if let Some(previous) = self.delegations.get(&voter).copied() {
+            let carried = self.carried_weight(&voter, previous.weight)?;
+            self.pass_on(previous.to, carried, false)?;
+        }
+        self.delegations.insert(voter, Delegation { to, weight });
+        let carried = self.carried_weight(&voter, weight)?;
+        self.pass_on(to, carried, true)
+    }
+
+    /// Returns the weight a delegator who did not vote passes on: their own `weight` and the
+    /// weight delegated to them.
+    fn carried_weight(&self, delegator: &Owner, weight: u64) -> Result<u64, PollError> {
+        let delegated = self.delegated.get(delegator).copied().unwrap_or(0);
+        checked_add(weight, delegated)
+    }
+
+    /// Adds `weight` to the weight delegated to `owner` and to each owner down the delegation
+    /// chain, until the first one who voted, whose ballot counts it. Removes it instead if
+    /// `add` is false.
+    fn pass_on(&mut self, mut owner: Owner, weight: u64, add: bool) -> Result<(), PollError> {
+        for _ in 0..=self.delegations.len() {
+            let delegated = self.delegated.entry(owner).or_insert(0);
+            *delegated = adjust(*delegated, weight, add)?;
+            if self.voters.contains_key(&owner) {
+                return self.count_weight(owner, weight, add);
+            }
+            match self.delegations.get(&owner) {
+                Some(delegation) => owner = delegation.to,
+                None => break,
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds the ballot of `voter`, with `weight`, to the stored tallies and total weight.
+    /// Removes it instead if `add` is false.
+    fn count_weight(&mut self, voter: Owner, weight: u64, add: bool) -> Result<(), PollError> {
+        let (counts, _) = self.count_ballot(&self.voters[&voter].ballot)?;
+        for (index, count) in counts {
+            let votes = count.checked_mul(weight).ok_or(PollError::WeightOverflow)?;
+            let tally = &mut self.options[index].votes;
+            *tally = adjust(*tally, votes, add)?;
+        }
+        self.total_weight = adjust(self.total_weight, weight, add)?;
+        Ok(())
+    }
+
+    /// Follows the delegations from `owner` to the first owner who voted directly, if any.
+    fn resolve_delegate(&self, mut owner: Owner) -> Option<Owner> {
+        for _ in 0..=self.delegations.len() {
+            if self.voters.contains_key(&owner) {
+                return Some(owner);
+            }
+            owner = self.delegations.get(&owner)?.to;
+        }
+        None
+    }
+
+    /// Checks that a poll with `voters` voters can accept one more.
+    fn check_voter_count(&self, voters: usize) -> Result<(), PollError> {
+        match self.settings.limits.max_voters {
+            Some(max_voters) if voters as u64 >= max_voters => Err(PollError::TooManyVoters),
+            _ => Ok(()),
+        }
+    }
+
+    /// Counts a ballot cast from `chain` at time `now` against the poll's rate limit.
+    pub fn check_rate_limit(&mut self, chain: ChainId, now: Timestamp) -> Result<(), PollError> {
+        let limit = self.settings.limits.rate_limit;
+        count_rate(&mut self.rate_windows, limit, chain, now)
+    }
+
+    /// Checks that `poll_id` is the poll hosted on this chain.
+    pub fn check_poll_id(&self, poll_id: u64) -> Result<(), PollError> {
+        if self.id == 0 || poll_id != self.id {
+            return Err(PollError::UnknownPoll(poll_id));
+        }
+        Ok(())
+    }
+
+    /// Records the commitment of `voter` to a secret ballot, see [`commitment_hash`].
+    pub fn commit(&mut self, voter: Owner, hash: [u8; 32]) -> Result<(), PollError> {
+        if !self.is_active {
+            return Err(PollError::PollClosed);
+        }
+        if !self.settings.secret_ballot {
+            return Err(PollError::NotSecretBallot);
+        }
+        if self.commitments.contains_key(&voter) {
+            return Err(PollError::AlreadyVoted);
+        }
+        self.check_voter_count(self.commitments.len())?;
+        self.commitments.insert(voter, hash);
+        Ok(())
+    }
+
+    /// Counts the secret ballot of `voter` once the poll is closed and until its revealing
+    /// period ends, if it matches their commitment.
+    pub fn reveal(
+        &mut self,
+        voter: Owner,
+        origin: VoteOrigin,
+        option: OptionRef,
+        salt: &str,
+        weight: u64,
+    ) -> Result<(), PollError> {
+        if self.is_active {
+            return Err(PollError::PollStillActive);
+        }
+        if self
+            .reveal_deadline
+            .is_some_and(|end| origin.timestamp >= end)
+        {
+            return Err(PollError::RevealClosed);
+        }
+        let hash = self
+            .commitments
+            .get(&voter)
+            .ok_or(PollError::NoCommitment)?;
+        if self.voters.contains_key(&voter) {
+            return Err(PollError::AlreadyVoted);
+        }
+        let name = &self.options[self.resolve(&option)?].name;
+        if commitment_hash(&voter, name, salt) != *hash {
+            return Err(PollError::CommitmentMismatch);
+        }
+        self.record(voter, origin, Ballot::Single { option }, weight)?;
+        self.outcome = Some(self.tally());
+        self.record_round();
+        Ok(())
+    }
+
+    /// Returns whether `voter` has cast a ballot or committed to a secret one.
+    pub fn has_voted(&self, voter: &Owner) -> bool {
+        self.voters.contains_key(voter) || self.commitments.contains_key(voter)
+    }
+
+    /// Returns the voters who committed a secret ballot but have not revealed it.
+    pub fn unrevealed(&self) -> Vec<Owner> {
+        let mut unrevealed: Vec<_> = self
+            .commitments
+            .keys()
+            .filter(|voter| !self.voters.contains_key(voter))
+            .copied()
+            .collect();
+        unrevealed.sort();
+        unrevealed
+    }
+
+    /// Counts `ballot` for `voter`, with the given `weight`, and appends it to the vote log.
+    fn record(
+        &mut self,
+        voter: Owner,
+        origin: VoteOrigin,
+        ballot: Ballot,
+        weight: u64,
+    ) -> Result<(), PollError> {
+        if self.voters.contains_key(&voter) {
+            return Err(PollError::AlreadyVoted);
+        }
+        if weight == 0 {
+            return Err(PollError::NoVotingWeight);
+        }
+        let (counts, credits_spent) = self.count_ballot(&ballot)?;
+        // The direct vote replaces the voter's delegation, along with what it carried.
+        if let Some(delegation) = self.delegations.get(&voter).copied() {
+            let carried = self.carried_weight(&voter, delegation.weight)?;
+            self.pass_on(delegation.to, carried, false)?;
+        }
+        self.total_votes += 1;
+        self.voters.insert(
+            voter,
+            VoterRecord {
+                ballot,
+                weight,
+                credits_spent,
+            },
+        );
+        self.log.push(VoteLogEntry {
+            voter,
+            origin,
+            votes: counts,
+            weight,
+        });
+        let weight = self.carried_weight(&voter, weight)?;
+        self.count_weight(voter, weight, true)
+    }
+
+    /// Tallies the vote log from scratch, adding to each ballot the weight delegated to its voter.
+    fn count_log(&self) -> Result<(Vec<u64>, u64), PollError> {
+        let mut delegated = HashMap::new();
+        for (delegator, delegation) in &self.delegations {
+            if self.voters.contains_key(delegator) {
+                continue;
+            }
+            if let Some(delegate) = self.resolve_delegate(delegation.to) {
+                let weight: &mut u64 = delegated.entry(delegate).or_insert(0);
+                *weight = checked_add(*weight, delegation.weight)?;
+            }
+        }
+        let mut tallies = vec![0; self.options.len()];
+        let mut total_weight = 0;
+        for entry in &self.log {
+            let delegated = delegated.get(&entry.voter).copied().unwrap_or(0);
+            let weight = checked_add(entry.weight, delegated)?;
+            for (index, count) in &entry.votes {
+                if let Some(tally) = tallies.get_mut(*index) {
+                    let votes = count.checked_mul(weight).ok_or(PollError::WeightOverflow)?;
+                    *tally = checked_add(*tally, votes)?;
+                }
+            }
+            total_weight = checked_add(total_weight, weight)?;
+        }
+        Ok((tallies, total_weight))
+    }
+
+    /// Recomputes the tallies from the vote log and the delegations, and compares them with
+    /// the stored counts.
+    pub fn audit(&self) -> Result<TallyAudit, PollError> {
+        let (tallies, total_weight) = self.count_log()?;
+        let total_votes = self.log.len() as u64;
+        let matches = total_votes == self.total_votes
+            && total_weight == self.total_weight
+            && tallies
+                .iter()
+                .zip(&self.options)
+                .all(|(tally, option)| *tally == option.votes);
+        Ok(TallyAudit {
+            tallies,
+            total_votes,
+            total_weight,
+            matches,
+        })
+    }
+
+    /// Returns the tallies of the poll.
+    pub fn results(&self) -> PollResults {
+        PollResults {
+            poll_id: self.id,
+            round: self.round,
+            question: self.question.clone(),
+            mode: self.mode.clone(),
+            tallies: self.options.clone(),
+            total_votes: self.total_votes,
+            total_weight: self.total_weight,
+            credits_spent: self
+                .voters
+                .values()
+                .map(|record| record.credits_spent)
+                .sum(),
+            unrevealed: self.unrevealed(),
+            outcome: self.outcome.clone(),
+            action_result: self.action_result.clone(),
+            action_cancelled: self.action_cancelled,
+        }
+    }
+
+    /// Returns the position of the option `option` refers to.
+    pub fn resolve(&self, option: &OptionRef) -> Result<usize, PollError> {
+        resolve(&self.options, option)
+    }
+
+    /// Validates `ballot` against the voting mode, returning the votes it adds to each option,
+    /// by position, and the credits it costs.
+    fn count_ballot(&self, ballot: &Ballot) -> Result<(Vec<(usize, u64)>, u64), PollError> {
+        count_ballot(&self.mode, &self.options, ballot)
+    }
+}
+
+impl Survey {
+    /// Starts a survey created by `creator` with the given title, questions and settings,
+    /// replacing the previous one. An active survey can only be replaced by its creator.
+    pub fn create(
+        &mut self,
+        creator: Option<Owner>,
+        title: String,
+        questions: Vec<NewQuestion>,
+        settings: SurveySettings,
+    ) -> Result<(), PollError> {
+        if self.is_active {
+            self.check_creator(creator)?;
+        }
+        if questions.is_empty() {
+            return Err(PollError::EmptySurvey);
+        }
+        let max_options = settings.limits.max_options.unwrap_or(MAX_OPTIONS);
+        for question in &questions {
+            if matches!(question.mode, VotingMode::TokenWeighted { .. }) {
+                return Err(PollError::SurveyModeUnsupported);
+            }
+            validate_options(&question.options)?;
+            if question.options.len() > max_options {
+                return Err(PollError::InvalidOptionCount(question.options.len()));
+            }
+        }
+        validate_limits(&settings.limits, creator)?;
+        self.id += 1;
+        self.title = title;
+        self.questions = questions
+            .into_iter()
+            .map(|question| SurveyQuestion {
+                question: question.question,
+                options: question.options.into_iter().map(PollOption::from).collect(),
+                mode: question.mode,
+            })
+            .collect();
+        self.responses.clear();
+        self.is_active = true;
+        self.creator = creator;
+        self.settings = settings;
+        self.rate_windows.clear();
+        Ok(())
+    }
+
+    /// Records the answers of `respondent`, one ballot per question. Either every answer is
+    /// counted or, if any of them is invalid, none is.
+    pub fn respond(&mut self, respondent: Owner, answers: Vec<Ballot>) -> Result<(), PollError> {
+        if !self.is_active {
+            return Err(PollError::SurveyClosed);
+        }
+        if self.responses.contains_key(&respondent) {
+            return Err(PollError::AlreadyVoted);
+        }
+        if let Some(max_voters) = self.settings.limits.max_voters {
+            if self.responses.len() as u64 >= max_voters {
+                return Err(PollError::TooManyVoters);
+            }
+        }
+        if answers.len() != self.questions.len() {
+            return Err(PollError::AnswerCountMismatch {
+                expected: self.questions.len(),
+                actual: answers.len(),
+            });
+        }
+        let response = self
+            .questions
+            .iter()
+            .zip(&answers)
+            .map(|(question, answer)| {
+                count_ballot(&question.mode, &question.options, answer).map(|(counts, _)| counts)
+            })
+            .collect::<Result<Vec<_>, PollError>>()?;
+        for (question, counts) in self.questions.iter_mut().zip(&response) {
+            for (index, count) in counts {
+                question.options[*index].votes += count;
+            }
+        }
+        self.responses.insert(respondent, response);
+        Ok(())
+    }
+
+    /// Checks that `signer` created the survey, if it has a creator.
+    fn check_creator(&self, signer: Option<Owner>) -> Result<(), PollError> {
+        match self.creator {
+            Some(creator) if signer != Some(creator) => Err(PollError::NotCreator),
+            _ => Ok(()),
+        }
+    }
+
+    /// Counts a response sent from `chain` at time `now` against the survey's rate limit.
+    pub fn check_rate_limit(&mut self, chain: ChainId, now: Timestamp) -> Result<(), PollError> {
+        let limit = self.settings.limits.rate_limit;
+        count_rate(&mut self.rate_windows, limit, chain, now)
+    }
+
+    /// Closes the survey on behalf of `signer`.
+    pub fn close(&mut self, signer: Option<Owner>) -> Result<(), PollError> {
+        self.check_creator(signer)?;
+        self.is_active = false;
+        Ok(())
+    }
+
+    /// Returns the counts of the options of question `other` among the respondents who gave
+    /// votes to option `option` of question `question`.
+    pub fn cross_tab(
+        &self,
+        question: usize,
+        option: usize,
+        other: usize,
+    ) -> Result<Vec<u64>, PollError> {
+        let options = &self
+            .questions
+            .get(question)
+            .ok_or(PollError::UnknownQuestion(question))?
+            .options;
+        if option >= options.len() {
+            return Err(PollError::UnknownOption(OptionRef::Index(option)));
+        }
+        let other_options = &self
+            .questions
+            .get(other)
+            .ok_or(PollError::UnknownQuestion(other))?
+            .options;
+        let mut tallies = vec![0; other_options.len()];
+        for response in self.responses.values() {
+            if response[question].iter().any(|(index, _)| *index == option) {
+                for (index, count) in &response[other] {
+                    tallies[*index] += count;
+                }
+            }
+        }
+        Ok(tallies)
+    }
+}
+
+/// Checks the number of options and that their names are non-empty and unique.
+fn validate_options(options: &[NewOption]) -> Result<(), PollError> {
+    if !(MIN_OPTIONS..=MAX_OPTIONS).contains(&options.len()) {
+        return Err(PollError::InvalidOptionCount(options.len()));
+    }
+    let mut names = HashSet::new();
+    for option in options {
+        if option.name.trim().is_empty() {
+            return Err(PollError::EmptyOptionName);
+        }
+        if !names.insert(&option.name) {
+            return Err(PollError::DuplicateOption(option.name.clone()));
+        }
+    }
+    Ok(())
+}
+
+/// Checks that `limits` are satisfiable, and that a vote fee has a `creator` to be paid to.
+fn validate_limits(limits: &PollLimits, creator: Option<Owner>) -> Result<(), PollError> {
+    if limits.max_voters == Some(0)
+        || limits
+            .rate_limit
+            .is_some_and(|limit| limit.votes == 0 || limit.window_micros == 0)
+    {
+        return Err(PollError::InvalidLimits);
+    }
+    if limits.vote_fee.is_some() && creator.is_none() {
+        return Err(PollError::NoFeeRecipient);
+    }
+    Ok(())
+}
+
+/// Counts a ballot cast from `chain` at time `now` against `limit`, in the rate-limiting
+/// `windows` of each chain.
+fn count_rate(
+    windows: &mut HashMap<ChainId, RateWindow>,
+    limit: Option<RateLimit>,
+    chain: ChainId,
+    now: Timestamp,
+) -> Result<(), PollError> {
+    let Some(limit) = limit else {
+        return Ok(());
+    };
+    let window = windows.entry(chain).or_insert(RateWindow {
+        start: now,
+        votes: 0,
+    });
+    if now.micros().saturating_sub(window.start.micros()) >= limit.window_micros {
+        *window = RateWindow {
+            start: now,
+            votes: 0,
+        };
+    }
+    if window.votes >= limit.votes {
+        return Err(PollError::RateLimited);
+    }
+    window.votes += 1;
+    Ok(())
+}
+
+/// Adds two weights, failing instead of overflowing.
+fn checked_add(a: u64, b: u64) -> Result<u64, PollError> {
+    a.checked_add(b).ok_or(PollError::WeightOverflow)
+}
+
+/// Adds `weight` to `value`, or subtracts it if `add` is false.
+fn adjust(value: u64, weight: u64, add: bool) -> Result<u64, PollError> {
+    if add {
+        checked_add(value, weight)
+    } else {
+        Ok(value.saturating_sub(weight))
+    }
+}
+
+/// Returns the position of the option `option` refers to among `options`.
+fn resolve(options: &[PollOption], option: &OptionRef) -> Result<usize, PollError> {
+    let index = match option {
+        OptionRef::Index(index) => Some(*index).filter(|index| *index < options.len()),
+        OptionRef::Name(name) => options.iter().position(|option| option.name == *name),
+    };
+    index.ok_or_else(|| PollError::UnknownOption(option.clone()))
+}
+
+/// Validates `ballot` against `mode`, returning the votes it adds to each of `options`, by
+/// position, and the credits it costs.
+fn count_ballot(
+    mode: &VotingMode,
+    options: &[PollOption],
+    ballot: &Ballot,
+) -> Result<(Vec<(usize, u64)>, u64), PollError> {
+    match (mode, ballot) {
+        (VotingMode::Plurality | VotingMode::TokenWeighted { .. }, Ballot::Single { option }) => {
+            Ok((vec![(resolve(options, option)?, 1)], 0))
+        }
+        (VotingMode::Approval, Ballot::Approval { options: approved }) => {
+            let approved = approved.iter().map(|option| (option, 1));
+            Ok((resolve_all(options, approved)?, 0))
+        }
+        (VotingMode::Quadratic { credits }, Ballot::Quadratic { votes }) => {
+            let votes = resolve_all(
+                options,
+                votes.iter().map(|(option, count)| (option, *count)),
+            )?;
+            let cost = votes
+                .iter()
+                .map(|(_, count)| count.saturating_mul(*count))
+                .fold(0u64, u64::saturating_add);
+            if cost > *credits {
+                return Err(PollError::InsufficientCredits {
+                    cost,
+                    budget: *credits,
+                });
+            }
+            Ok((votes, cost))
+        }
+        _ => Err(PollError::BallotMismatch),
+    }
+}
+
+/// Resolves the options of a ballot, checking that each appears once and dropping those given
+/// no votes.
+fn resolve_all<'a>(
+    options: &[PollOption],
+    votes: impl IntoIterator<Item = (&'a OptionRef, u64)>,
+) -> Result<Vec<(usize, u64)>, PollError> {
+    let mut seen = HashSet::new();
+    let mut resolved = Vec::new();
+    for (option, count) in votes {
+        let index = resolve(options, option)?;
+        if !seen.insert(index) {
+            return Err(PollError::DuplicateOption(options[index].name.clone()));
+        }
+        if count > 0 {
+            resolved.push((index, count));
+        }
+    }
+    if resolved.is_empty() {
+        return Err(PollError::EmptyBallot);
+    }
+    Ok(resolved)
 }
 
 /// The operation types that can be sent to the poll application.
 #[derive(Debug, Deserialize, Serialize)]
 pub enum PollOperation {
-    /// Creates a new poll with the given question and options.
-    CreatePoll { question: String, options: Vec<String> },
-    /// Votes for a specific option.
-    Vote { option: String },
+    /// Creates a new poll with the given question, options, voting mode and settings.
+    CreatePoll {
+        question: String,
+        options: Vec<NewOption>,
+        mode: VotingMode,
+        settings: PollSettings,
+    },
+    /// Creates a new poll with the given question, laid out by a template.
+    CreateFromTemplate {
+        question: String,
+        template: PollTemplate,
+        settings: PollSettings,
+    },
+    /// Casts a ballot.
+    Vote { ballot: Ballot },
+    /// Casts a ballot in the poll `poll_id` hosted on `home_chain`.
+    VoteRemote {
+        home_chain: ChainId,
+        poll_id: u64,
+        ballot: Ballot,
+    },
+    /// Commits to a secret ballot while the poll is active.
+    Commit { hash: [u8; 32] },
+    /// Reveals a committed ballot after the poll is closed.
+    Reveal { option: OptionRef, salt: String },
+    /// Delegates the signer's vote, or weight, to another owner.
+    Delegate { to: Owner },
     /// Closes the poll.
     ClosePoll,
-    /// Reopens the poll.
+    /// Starts a survey with the given title, questions and settings, replacing the previous
+    /// one.
+    CreateSurvey {
+        title: String,
+        questions: Vec<NewQuestion>,
+        settings: SurveySettings,
+    },
+    /// Answers every question of the survey, in order.
+    Respond { answers: Vec<Ballot> },
+    /// Closes the survey.
+    CloseSurvey,
+    /// Reopens the poll for a new round, if its settings allow it.
     ReopenPoll,
+    /// Adds a voter to the allow-list. Only the poll creator can do this.
+    AddEligibleVoter { voter: EligibleVoter },
+    /// Removes a voter from the allow-list. Only the poll creator can do this.
+    RemoveEligibleVoter { voter: EligibleVoter },
+    /// Executes the action of a poll that passed.
+    ExecuteAction,
+    /// Cancels the action of a closed poll, so that it is never executed. Only the poll
+    /// creator can do this.
+    CancelAction,
 }
 
 /// The message types that can be sent to the poll application.
 #[derive(Debug, Deserialize, Serialize)]
 pub enum PollMessage {
-    /// Votes for a specific option.
-    Vote { option: String },
+    /// Casts a ballot relayed from a participant chain in the poll `poll_id`.
+    Vote { poll_id: u64, ballot: Ballot },
     /// Closes the poll.
     ClosePoll,
 }
@@ -45,36 +1704,320 @@ pub enum PollMessage {
 /// The application call types that can be made to the poll application.
 #[derive(Debug, Deserialize, Serialize)]
 pub enum PollApplicationCall {
-    /// Creates a new poll with the given question and options.
-    CreatePoll { question: String, options: Vec<String> },
-    /// Votes for a specific option.
-    Vote { option: String },
+    /// Creates a new poll with the given question, options, voting mode and settings.
+    CreatePoll {
+        question: String,
+        options: Vec<NewOption>,
+        mode: VotingMode,
+        settings: PollSettings,
+    },
+    /// Creates a new poll with the given question, laid out by a template.
+    CreateFromTemplate {
+        question: String,
+        template: PollTemplate,
+        settings: PollSettings,
+    },
+    /// Casts a ballot.
+    Vote { ballot: Ballot },
+    /// Commits to a secret ballot while the poll is active.
+    Commit { hash: [u8; 32] },
+    /// Reveals a committed ballot after the poll is closed.
+    Reveal { option: OptionRef, salt: String },
+    /// Delegates the signer's vote, or weight, to another owner.
+    Delegate { to: Owner },
+    /// Adds a voter to the allow-list. Only the poll creator can do this.
+    AddEligibleVoter { voter: EligibleVoter },
+    /// Removes a voter from the allow-list. Only the poll creator can do this.
+    RemoveEligibleVoter { voter: EligibleVoter },
+    /// Executes the action of a poll that passed, returning the action's result.
+    ExecuteAction,
+    /// Cancels the action of a closed poll, so that it is never executed. Only the poll
+    /// creator can do this.
+    CancelAction,
     /// Gets the current poll results.
     GetResults,
     /// Gets the poll question.
     GetQuestion,
     /// Checks if the poll is active.
     IsActive,
-    /// Closes the poll.
+    /// Gets the outcome of the poll, once it is closed.
+    GetOutcome,
+    /// Opens a session bound to the current poll.
+    OpenSession,
+    /// Closes the poll, returning its outcome.
     ClosePoll,
+    /// Starts a survey with the given title, questions and settings, replacing the previous
+    /// one.
+    CreateSurvey {
+        title: String,
+        questions: Vec<NewQuestion>,
+        settings: SurveySettings,
+    },
+    /// Answers every question of the survey, in order.
+    Respond { answers: Vec<Ballot> },
+    /// Closes the survey.
+    CloseSurvey,
+    /// Gets the results recorded when each poll round closed.
+    GetHistory,
+    /// Gets the survey and its per-question counts.
+    GetSurvey,
+    /// Gets the counts of the options of question `other` among the respondents who chose
+    /// option `option` of question `question`.
+    CrossTab {
+        question: usize,
+        option: usize,
+        other: usize,
+    },
+}
+
+/// A session opened by a caller application, bound to the poll that was current when it was
+/// opened. It stops accepting ballots once another poll is created.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PollSession {
+    /// The poll the session is bound to.
+    pub poll_id: u64,
 }
 
 /// The session call types that can be made to the poll application.
 #[derive(Debug, Deserialize, Serialize)]
 pub enum PollSessionCall {
-    /// Votes for a specific option.
-    Vote { option: String },
+    /// Casts a ballot in the session's poll.
+    Vote { ballot: Ballot },
     /// Gets the current poll results.
     GetResults,
+    /// Closes the session.
+    Close,
 }
 
 /// The effect types that can be sent by the poll application.
 #[derive(Debug, Deserialize, Serialize)]
 pub enum PollEffect {
-    /// Votes for a specific option.
-    Vote { option: String },
+    /// Relays a ballot to the poll `poll_id` on `home_chain`, as a [`PollMessage::Vote`].
+    Vote {
+        home_chain: ChainId,
+        poll_id: u64,
+        ballot: Ballot,
+    },
     /// Closes the poll.
     ClosePoll,
+    /// Announces the outcome of the poll `poll_id` when it closes, and again whenever a secret
+    /// ballot revealed afterwards changes it.
+    Outcome { poll_id: u64, outcome: Outcome },
+}
+
+impl Poll {
+    /// Reads the balances of the snapshot holders, if `mode` asks for a snapshot.
+    async fn take_snapshot(
+        mode: &VotingMode,
+    ) -> Result<HashMap<Owner, u64>, linera_sdk::base::Error> {
+        let mut snapshot = HashMap::new();
+        if let VotingMode::TokenWeighted {
+            token,
+            weighting: Weighting::Snapshot { holders },
+        } = mode
+        {
+            for holder in holders {
+                snapshot.insert(*holder, Self::token_balance(*token, *holder).await?);
+            }
+        }
+        Ok(snapshot)
+    }
+
+    /// Returns the weight of a ballot cast by `voter` under the current voting mode.
+    async fn voting_weight(&self, voter: Owner) -> Result<u64, linera_sdk::base::Error> {
+        match &self.mode {
+            VotingMode::TokenWeighted {
+                token,
+                weighting: Weighting::AtVote,
+            } => Self::token_balance(*token, voter).await,
+            VotingMode::TokenWeighted {
+                weighting: Weighting::Snapshot { .. },
+                ..
+            } => Ok(self.snapshot.get(&voter).copied().unwrap_or_default()),
+            _ => Ok(1),
+        }
+    }
+
+    /// Reads the balance of `owner` in the fungible-token application `token`.
+    async fn token_balance(
+        token: ApplicationId,
+        owner: Owner,
+    ) -> Result<u64, linera_sdk::base::Error> {
+        let result = system_api::call_application::<_, u64>(
+            true,
+            token,
+            &FungibleTokenCall::Balance { owner },
+            vec![],
+        )
+        .await?;
+        Ok(result.value.unwrap_or_default())
+    }
+
+    /// Executes the action of the poll and records its result.
+    async fn execute_action(&mut self) -> Result<Vec<u8>, linera_sdk::base::Error> {
+        let action = self.take_action(system_api::current_system_time())?;
+        let result =
+            system_api::call_application_raw(false, action.application_id, &action.call, vec![])
+                .await?;
+        let result = result.value.unwrap_or_default();
+        self.action_result = Some(result.clone());
+        Ok(result)
+    }
+
+    /// Checks that `voter`, voting from `chain`, is admitted by `eligibility`.
+    async fn check_eligibility(
+        eligibility: &Eligibility,
+        voter: Owner,
+        chain: ChainId,
+    ) -> Result<(), linera_sdk::base::Error> {
+        let token_balance = match eligibility {
+            Eligibility::TokenHolders { token, .. } => {
+                Some(Self::token_balance(*token, voter).await?)
+            }
+            _ => None,
+        };
+        if !eligibility.admits(&voter, &chain, token_balance) {
+            return Err(PollError::NotEligible.into());
+        }
+        Ok(())
+    }
+
+    /// Casts `ballot` on behalf of the authenticated `signer`, voting from `chain`.
+    async fn cast(
+        &mut self,
+        signer: Option<Owner>,
+        chain: ChainId,
+        ballot: Ballot,
+    ) -> Result<(), linera_sdk::base::Error> {
+        let voter = signer.ok_or(PollError::MissingSigner)?;
+        let origin = Self::origin(chain);
+        self.check_rate_limit(chain, origin.timestamp)?;
+        Self::check_eligibility(&self.settings.eligibility, voter, chain).await?;
+        let weight = self.voting_weight(voter).await?;
+        self.vote(voter, origin, ballot, weight)?;
+        Self::charge_fee(self.settings.limits.vote_fee, voter, self.creator).await?;
+        self.count_participant().await;
+        Ok(())
+    }
+
+    /// Answers the survey on behalf of the authenticated `signer`, responding from `chain`.
+    async fn respond_as(
+        &mut self,
+        signer: Option<Owner>,
+        chain: ChainId,
+        answers: Vec<Ballot>,
+    ) -> Result<(), linera_sdk::base::Error> {
+        let respondent = signer.ok_or(PollError::MissingSigner)?;
+        self.survey
+            .check_rate_limit(chain, system_api::current_system_time())?;
+        Self::check_eligibility(&self.survey.settings.eligibility, respondent, chain).await?;
+        self.survey.respond(respondent, answers)?;
+        let fee = self.survey.settings.limits.vote_fee;
+        Self::charge_fee(fee, respondent, self.survey.creator).await
+    }
+
+    /// Delegates the vote of the authenticated `signer`, voting from `chain`, to `to`.
+    async fn delegate_as(
+        &mut self,
+        signer: Option<Owner>,
+        chain: ChainId,
+        to: Owner,
+    ) -> Result<(), linera_sdk::base::Error> {
+        let voter = signer.ok_or(PollError::MissingSigner)?;
+        self.check_rate_limit(chain, system_api::current_system_time())?;
+        Self::check_eligibility(&self.settings.eligibility, voter, chain).await?;
+        let weight = self.voting_weight(voter).await?;
+        self.delegate(voter, to, weight)?;
+        Ok(())
+    }
+
+    /// Commits to a secret ballot on behalf of the authenticated `signer`, voting from `chain`.
+    async fn commit_as(
+        &mut self,
+        signer: Option<Owner>,
+        chain: ChainId,
+        hash: [u8; 32],
+    ) -> Result<(), linera_sdk::base::Error> {
+        let voter = signer.ok_or(PollError::MissingSigner)?;
+        self.check_rate_limit(chain, system_api::current_system_time())?;
+        Self::check_eligibility(&self.settings.eligibility, voter, chain).await?;
+        self.commit(voter, hash)?;
+        Self::charge_fee(self.settings.limits.vote_fee, voter, self.creator).await?;
+        self.count_participant().await;
+        Ok(())
+    }
+
+    /// Transfers the vote fee `fee`, if any, from `voter` to `creator`.
+    async fn charge_fee(
+        fee: Option<VoteFee>,
+        voter: Owner,
+        creator: Option<Owner>,
+    ) -> Result<(), linera_sdk::base::Error> {
+        let Some(fee) = fee else {
+            return Ok(());
+        };
+        let creator = creator.ok_or(PollError::NoFeeRecipient)?;
+        let transfer = FungibleTokenCall::Transfer {
+            owner: voter,
+            amount: fee.amount,
+            target: creator,
+        };
+        system_api::call_application::<_, ()>(true, fee.token, &transfer, vec![]).await?;
+        Ok(())
+    }
+
+    /// Counts a participant with the poll's participant counter, if any, and records the count
+    /// it returns. The count is best-effort: if the counter fails, for example because the
+    /// poll's grant was revoked or its quota is used up, the last count is kept and the ballot
+    /// still counts.
+    async fn count_participant(&mut self) {
+        let Some(counter) = self.settings.participant_counter else {
+            return;
+        };
+        let call = CounterApplicationCall::IncrementKey {
+            key: format!("poll-{}", self.id),
+        };
+        if let Ok(result) =
+            system_api::call_application::<_, u64>(true, counter, &call, vec![]).await
+        {
+            if let Some(participants) = result.value {
+                self.participants = Some(participants);
+            }
+        }
+    }
+
+    /// Returns the effect announcing the current outcome, if the poll has one.
+    fn outcome_effects(&self) -> Vec<PollEffect> {
+        self.outcome
+            .iter()
+            .map(|outcome| PollEffect::Outcome {
+                poll_id: self.id,
+                outcome: outcome.clone(),
+            })
+            .collect()
+    }
+
+    /// Reveals the secret ballot of the authenticated `signer`, revealing from `chain`.
+    async fn reveal_as(
+        &mut self,
+        signer: Option<Owner>,
+        chain: ChainId,
+        option: OptionRef,
+        salt: String,
+    ) -> Result<(), linera_sdk::base::Error> {
+        let voter = signer.ok_or(PollError::MissingSigner)?;
+        let weight = self.voting_weight(voter).await?;
+        self.reveal(voter, Self::origin(chain), option, &salt, weight)?;
+        Ok(())
+    }
+
+    /// Returns the origin of a ballot cast from `chain` now.
+    fn origin(chain: ChainId) -> VoteOrigin {
+        VoteOrigin {
+            chain_id: chain,
+            timestamp: system_api::current_system_time(),
+        }
+    }
 }
 
 impl Contract for Poll {
@@ -83,56 +2026,156 @@ impl Contract for Poll {
     type ApplicationCall = PollApplicationCall;
     type SessionCall = PollSessionCall;
     type Effect = PollEffect;
-    type SessionState = ();
+    type SessionState = PollSession;
     type ApplicationState = Poll;
 
     async fn initialize(
         &mut self,
-        _context: &OperationContext,
+        context: &OperationContext,
         _argument: (),
         _storage: ViewStateStorage<Self>,
     ) -> Result<ExecutionResult<Self::Effect>, linera_sdk::base::Error> {
         // Initialize with default poll
-        self.question = "What is your favorite option?".to_string();
-        self.options.insert("Option A".to_string(), 0);
-        self.options.insert("Option B".to_string(), 0);
-        self.options.insert("Option C".to_string(), 0);
-        self.total_votes = 0;
-        self.is_active = true;
-        
+        self.create(
+            context.authenticated_signer,
+            "What is your favorite option?".to_string(),
+            vec!["Option A".into(), "Option B".into(), "Option C".into()],
+            VotingMode::Plurality,
+            PollSettings::default(),
+            HashMap::new(),
+            system_api::current_system_time(),
+        )?;
+
         Ok(ExecutionResult::default())
     }
 
     async fn execute_operation(
         &mut self,
-        _context: &OperationContext,
+        context: &OperationContext,
         operation: Self::Operation,
         _storage: ViewStateStorage<Self>,
     ) -> Result<ExecutionResult<Self::Effect>, linera_sdk::base::Error> {
         match operation {
-            PollOperation::CreatePoll { question, options } => {
-                self.question = question;
-                self.options.clear();
-                for option in options {
-                    self.options.insert(option, 0);
-                }
-                self.total_votes = 0;
-                self.is_active = true;
+            PollOperation::CreatePoll {
+                question,
+                options,
+                mode,
+                settings,
+            } => {
+                let snapshot = Self::take_snapshot(&mode).await?;
+                self.create(
+                    context.authenticated_signer,
+                    question,
+                    options,
+                    mode,
+                    settings,
+                    snapshot,
+                    system_api::current_system_time(),
+                )?;
                 Ok(ExecutionResult::default())
             }
-            PollOperation::Vote { option } => {
-                if self.is_active && self.options.contains_key(&option) {
-                    *self.options.get_mut(&option).unwrap() += 1;
-                    self.total_votes += 1;
-                }
+            PollOperation::CreateFromTemplate {
+                question,
+                template,
+                settings,
+            } => {
+                let (options, mode) = template.into_parts();
+                let snapshot = Self::take_snapshot(&mode).await?;
+                self.create(
+                    context.authenticated_signer,
+                    question,
+                    options,
+                    mode,
+                    settings,
+                    snapshot,
+                    system_api::current_system_time(),
+                )?;
+                Ok(ExecutionResult::default())
+            }
+            PollOperation::Vote { ballot } => {
+                self.cast(context.authenticated_signer, context.chain_id, ballot)
+                    .await?;
+                Ok(ExecutionResult::default())
+            }
+            PollOperation::VoteRemote {
+                home_chain,
+                poll_id,
+                ballot,
+            } => {
+                // The home chain checks the ballot, but only signed votes are worth relaying.
+                context
+                    .authenticated_signer
+                    .ok_or(PollError::MissingSigner)?;
+                let effects = vec![PollEffect::Vote {
+                    home_chain,
+                    poll_id,
+                    ballot,
+                }];
+                Ok(ExecutionResult { effects })
+            }
+            PollOperation::Commit { hash } => {
+                self.commit_as(context.authenticated_signer, context.chain_id, hash)
+                    .await?;
                 Ok(ExecutionResult::default())
             }
+            PollOperation::Reveal { option, salt } => {
+                self.reveal_as(context.authenticated_signer, context.chain_id, option, salt)
+                    .await?;
+                let effects = self.outcome_effects();
+                Ok(ExecutionResult { effects })
+            }
             PollOperation::ClosePoll => {
-                self.is_active = false;
+                self.close(
+                    context.authenticated_signer,
+                    system_api::current_system_time(),
+                )?;
+                let effects = self.outcome_effects();
+                Ok(ExecutionResult { effects })
+            }
+            PollOperation::CreateSurvey {
+                title,
+                questions,
+                settings,
+            } => {
+                self.survey
+                    .create(context.authenticated_signer, title, questions, settings)?;
+                Ok(ExecutionResult::default())
+            }
+            PollOperation::Respond { answers } => {
+                self.respond_as(context.authenticated_signer, context.chain_id, answers)
+                    .await?;
+                Ok(ExecutionResult::default())
+            }
+            PollOperation::CloseSurvey => {
+                self.survey.close(context.authenticated_signer)?;
                 Ok(ExecutionResult::default())
             }
             PollOperation::ReopenPoll => {
-                self.is_active = true;
+                self.reopen(
+                    context.authenticated_signer,
+                    system_api::current_system_time(),
+                )?;
+                Ok(ExecutionResult::default())
+            }
+            PollOperation::Delegate { to } => {
+                self.delegate_as(context.authenticated_signer, context.chain_id, to)
+                    .await?;
+                Ok(ExecutionResult::default())
+            }
+            PollOperation::AddEligibleVoter { voter } => {
+                self.add_eligible(context.authenticated_signer, voter)?;
+                Ok(ExecutionResult::default())
+            }
+            PollOperation::RemoveEligibleVoter { voter } => {
+                self.remove_eligible(context.authenticated_signer, voter)?;
+                Ok(ExecutionResult::default())
+            }
+            PollOperation::ExecuteAction => {
+                self.execute_action().await?;
+                Ok(ExecutionResult::default())
+            }
+            PollOperation::CancelAction => {
+                self.cancel_action(context.authenticated_signer)?;
                 Ok(ExecutionResult::default())
             }
         }
@@ -140,104 +2183,291 @@ impl Contract for Poll {
 
     async fn execute_message(
         &mut self,
-        _context: &MessageContext,
+        context: &MessageContext,
         message: Self::Message,
         _storage: ViewStateStorage<Self>,
     ) -> Result<ExecutionResult<Self::Effect>, linera_sdk::base::Error> {
         match message {
-            PollMessage::Vote { option } => {
-                if self.is_active && self.options.contains_key(&option) {
-                    *self.options.get_mut(&option).unwrap() += 1;
-                    self.total_votes += 1;
-                }
+            PollMessage::Vote { poll_id, ballot } => {
+                self.check_poll_id(poll_id)?;
+                let origin = context.message_id.chain_id;
+                self.cast(context.authenticated_signer, origin, ballot)
+                    .await?;
                 Ok(ExecutionResult::default())
             }
             PollMessage::ClosePoll => {
-                self.is_active = false;
-                Ok(ExecutionResult::default())
+                self.close(
+                    context.authenticated_signer,
+                    system_api::current_system_time(),
+                )?;
+                let effects = self.outcome_effects();
+                Ok(ExecutionResult { effects })
             }
         }
     }
 
     async fn handle_application_call(
         &mut self,
-        _context: &CalleeContext,
+        context: &CalleeContext,
         call: Self::ApplicationCall,
         _storage: ViewStateStorage<Self>,
     ) -> Result<ApplicationCallResult<Self::Effect>, linera_sdk::base::Error> {
         match call {
-            PollApplicationCall::CreatePoll { question, options } => {
-                self.question = question;
-                self.options.clear();
-                for option in options {
-                    self.options.insert(option, 0);
-                }
-                self.total_votes = 0;
-                self.is_active = true;
+            PollApplicationCall::CreatePoll {
+                question,
+                options,
+                mode,
+                settings,
+            } => {
+                let snapshot = Self::take_snapshot(&mode).await?;
+                self.create(
+                    context.authenticated_signer,
+                    question,
+                    options,
+                    mode,
+                    settings,
+                    snapshot,
+                    system_api::current_system_time(),
+                )?;
                 Ok(ApplicationCallResult::default())
             }
-            PollApplicationCall::Vote { option } => {
-                if self.is_active && self.options.contains_key(&option) {
-                    *self.options.get_mut(&option).unwrap() += 1;
-                    self.total_votes += 1;
-                }
+            PollApplicationCall::CreateFromTemplate {
+                question,
+                template,
+                settings,
+            } => {
+                let (options, mode) = template.into_parts();
+                let snapshot = Self::take_snapshot(&mode).await?;
+                self.create(
+                    context.authenticated_signer,
+                    question,
+                    options,
+                    mode,
+                    settings,
+                    snapshot,
+                    system_api::current_system_time(),
+                )?;
+                Ok(ApplicationCallResult::default())
+            }
+            PollApplicationCall::Vote { ballot } => {
+                self.cast(context.authenticated_signer, context.chain_id, ballot)
+                    .await?;
+                Ok(ApplicationCallResult::default())
+            }
+            PollApplicationCall::Commit { hash } => {
+                self.commit_as(context.authenticated_signer, context.chain_id, hash)
+                    .await?;
                 Ok(ApplicationCallResult::default())
             }
-            PollApplicationCall::GetResults => {
+            PollApplicationCall::Reveal { option, salt } => {
+                self.reveal_as(context.authenticated_signer, context.chain_id, option, salt)
+                    .await?;
+                let effects = self.outcome_effects();
                 Ok(ApplicationCallResult {
-                    value: Some(self.options.clone()),
-                    effects: vec![],
+                    effects,
+                    ..Default::default()
                 })
             }
-            PollApplicationCall::GetQuestion => {
+            PollApplicationCall::GetResults => Ok(ApplicationCallResult {
+                value: Some(self.results()),
+                effects: vec![],
+            }),
+            PollApplicationCall::GetQuestion => Ok(ApplicationCallResult {
+                value: Some(self.question.clone()),
+                effects: vec![],
+            }),
+            PollApplicationCall::IsActive => Ok(ApplicationCallResult {
+                value: Some(self.is_active),
+                effects: vec![],
+            }),
+            PollApplicationCall::Delegate { to } => {
+                self.delegate_as(context.authenticated_signer, context.chain_id, to)
+                    .await?;
+                Ok(ApplicationCallResult::default())
+            }
+            PollApplicationCall::AddEligibleVoter { voter } => {
+                self.add_eligible(context.authenticated_signer, voter)?;
+                Ok(ApplicationCallResult::default())
+            }
+            PollApplicationCall::RemoveEligibleVoter { voter } => {
+                self.remove_eligible(context.authenticated_signer, voter)?;
+                Ok(ApplicationCallResult::default())
+            }
+            PollApplicationCall::ExecuteAction => {
+                let result = self.execute_action().await?;
                 Ok(ApplicationCallResult {
-                    value: Some(self.question.clone()),
+                    value: Some(result),
                     effects: vec![],
                 })
             }
-            PollApplicationCall::IsActive => {
+            PollApplicationCall::CancelAction => {
+                self.cancel_action(context.authenticated_signer)?;
+                Ok(ApplicationCallResult::default())
+            }
+            PollApplicationCall::GetOutcome => Ok(ApplicationCallResult {
+                value: Some(self.outcome.clone()),
+                effects: vec![],
+            }),
+            PollApplicationCall::OpenSession => {
+                self.check_poll_id(self.id)?;
                 Ok(ApplicationCallResult {
-                    value: Some(self.is_active),
-                    effects: vec![],
+                    create_sessions: vec![PollSession { poll_id: self.id }],
+                    ..Default::default()
                 })
             }
             PollApplicationCall::ClosePoll => {
-                self.is_active = false;
+                let outcome = self.close(
+                    context.authenticated_signer,
+                    system_api::current_system_time(),
+                )?;
+                Ok(ApplicationCallResult {
+                    value: Some(outcome),
+                    effects: self.outcome_effects(),
+                })
+            }
+            PollApplicationCall::CreateSurvey {
+                title,
+                questions,
+                settings,
+            } => {
+                self.survey
+                    .create(context.authenticated_signer, title, questions, settings)?;
+                Ok(ApplicationCallResult::default())
+            }
+            PollApplicationCall::Respond { answers } => {
+                self.respond_as(context.authenticated_signer, context.chain_id, answers)
+                    .await?;
+                Ok(ApplicationCallResult::default())
+            }
+            PollApplicationCall::CloseSurvey => {
+                self.survey.close(context.authenticated_signer)?;
                 Ok(ApplicationCallResult::default())
             }
+            PollApplicationCall::GetHistory => Ok(ApplicationCallResult {
+                value: Some(self.history.clone()),
+                effects: vec![],
+            }),
+            PollApplicationCall::GetSurvey => Ok(ApplicationCallResult {
+                value: Some(self.survey.clone()),
+                effects: vec![],
+            }),
+            PollApplicationCall::CrossTab {
+                question,
+                option,
+                other,
+            } => Ok(ApplicationCallResult {
+                value: Some(self.survey.cross_tab(question, option, other)?),
+                effects: vec![],
+            }),
         }
     }
 
     async fn handle_session_call(
         &mut self,
-        _context: &CalleeContext,
-        _call: Self::SessionCall,
+        context: &CalleeContext,
+        session: &mut Self::SessionState,
+        call: Self::SessionCall,
         _storage: ViewStateStorage<Self>,
     ) -> Result<SessionCallResult<Self::Effect>, linera_sdk::base::Error> {
-        Err(linera_sdk::base::Error::default())
+        match call {
+            PollSessionCall::Vote { ballot } => {
+                self.check_poll_id(session.poll_id)?;
+                self.cast(context.authenticated_signer, context.chain_id, ballot)
+                    .await?;
+                Ok(SessionCallResult::default())
+            }
+            PollSessionCall::GetResults => Ok(SessionCallResult {
+                inner: ApplicationCallResult {
+                    value: Some(self.results()),
+                    effects: vec![],
+                },
+                close_session: false,
+            }),
+            PollSessionCall::Close => Ok(SessionCallResult {
+                close_session: true,
+                ..Default::default()
+            }),
+        }
     }
 
     async fn handle_effect(
         &mut self,
         _context: &EffectContext,
-        _effect: Self::Effect,
+        effect: Self::Effect,
         _storage: ViewStateStorage<Self>,
     ) -> Result<ExecutionResult<Self::Effect>, linera_sdk::base::Error> {
-        Ok(ExecutionResult::default())
+        match effect {
+            PollEffect::Vote {
+                home_chain,
+                poll_id,
+                ballot,
+            } => {
+                // Authenticated, so that the home chain counts the ballot as the signer's.
+                let message = PollMessage::Vote { poll_id, ballot };
+                system_api::send_message(true, home_chain, &message);
+                Ok(ExecutionResult::default())
+            }
+            PollEffect::ClosePoll | PollEffect::Outcome { .. } => Ok(ExecutionResult::default()),
+        }
     }
 }
 
 /// The state of the poll application.
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct PollView {
+    /// Identifies the current poll. Incremented whenever a poll is created, so 0 means that no
+    /// poll was ever created on this chain.
+    pub id: u64,
     /// The poll question.
     pub question: String,
-    /// The poll options and their vote counts.
-    pub options: HashMap<String, u64>,
-    /// Total number of votes cast.
+    /// The poll options and their vote counts, in creation order.
+    pub options: Vec<PollOption>,
+    /// Total number of voters.
     pub total_votes: u64,
+    /// Total weight of all ballots. Equal to `total_votes` unless the poll is token-weighted.
+    pub total_weight: u64,
     /// Whether the poll is still active.
     pub is_active: bool,
+    /// How ballots are cast and counted.
+    pub mode: VotingMode,
+    /// The ballot cast by each voter.
+    pub voters: HashMap<Owner, VoterRecord>,
+    /// Token balances taken when a [`Weighting::Snapshot`] poll was created.
+    pub snapshot: HashMap<Owner, u64>,
+    /// The settings the poll was created with.
+    pub settings: PollSettings,
+    /// The ballot commitments of a secret poll, by voter.
+    pub commitments: HashMap<Owner, [u8; 32]>,
+    /// The outcome computed when the poll was closed, updated as secret ballots are revealed.
+    pub outcome: Option<Outcome>,
+    /// The owner who created the poll, and who manages its allow-list.
+    pub creator: Option<Owner>,
+    /// The value returned by the poll's [`ProposalAction`], once it was executed.
+    pub action_result: Option<Vec<u8>>,
+    /// Whether the creator cancelled the poll's [`ProposalAction`] instead of executing it.
+    pub action_cancelled: bool,
+    /// When the revealing period of a closed secret poll ends. Ballots not revealed by then
+    /// count as abstentions.
+    pub reveal_deadline: Option<Timestamp>,
+    /// Every counted ballot, in the order it was counted.
+    pub log: Vec<VoteLogEntry>,
+    /// The delegation made by each delegating voter.
+    pub delegations: HashMap<Owner, Delegation>,
+    /// The weight delegated to each owner, directly or through other delegators, by
+    /// delegators who did not vote.
+    pub delegated: HashMap<Owner, u64>,
+    /// The survey hosted on this chain, alongside the poll.
+    pub survey: Survey,
+    /// The round of the current poll. Starts at 1, and a new round starts whenever the poll is
+    /// reopened with [`ReopenPolicy::NewRound`].
+    pub round: u64,
+    /// The results recorded when each round closed, of every poll created on this chain.
+    pub history: Vec<PollResults>,
+    /// The current rate-limiting window of each chain ballots were cast from.
+    pub rate_windows: HashMap<ChainId, RateWindow>,
+    /// The number of participants of the current poll, as last returned by its participant
+    /// counter.
+    pub participants: Option<u64>,
 }
 
 impl linera_sdk::contract::WitInterface for Poll {
@@ -252,8 +2482,8 @@ mod tests {
     fn test_poll_creation() {
         let mut poll = Poll::default();
         poll.question = "Test question".to_string();
-        poll.options.insert("Option 1".to_string(), 0);
-        poll.options.insert("Option 2".to_string(), 0);
+        poll.options.push(PollOption::new("Option 1"));
+        poll.options.push(PollOption::new("Option 2"));
         poll.is_active = true;
         
         assert_eq!(poll.question, "Test question");
@@ -264,15 +2494,15 @@ mod tests {
     #[test]
     fn test_poll_voting() {
         let mut poll = Poll::default();
-        poll.options.insert("Option A".to_string(), 0);
-        poll.options.insert("Option B".to_string(), 0);
+        poll.options.push(PollOption::new("Option A"));
+        poll.options.push(PollOption::new("Option B"));
         poll.is_active = true;
         
         // Vote for Option A
-        *poll.options.get_mut("Option A").unwrap() += 1;
+        poll.options[0].votes += 1;
         poll.total_votes += 1;
         
-        assert_eq!(poll.options["Option A"], 1);
+        assert_eq!(poll.options[0].votes, 1);
         assert_eq!(poll.total_votes, 1);
     }
 
@@ -285,4 +2515,820 @@ mod tests {
         
         assert!(!poll.is_active);
     }
+
+    fn owner(byte: u8) -> Owner {
+        Owner::from([byte; 32])
+    }
+
+    fn new_poll(mode: VotingMode, settings: PollSettings) -> Poll {
+        let options = vec!["A".into(), "B".into(), "C".into()];
+        let mut poll = Poll::default();
+        poll.create(
+            Some(owner(0)),
+            "Q".to_string(),
+            options,
+            mode,
+            settings,
+            HashMap::new(),
+            now(),
+        )
+        .unwrap();
+        poll
+    }
+
+    fn now() -> Timestamp {
+        Timestamp::from(0)
+    }
+
+    fn origin() -> VoteOrigin {
+        VoteOrigin {
+            chain_id: ChainId::from([1; 32]),
+            timestamp: Timestamp::from(0),
+        }
+    }
+
+    fn name(option: &str) -> OptionRef {
+        OptionRef::Name(option.to_string())
+    }
+
+    fn single(option: &str) -> Ballot {
+        Ballot::Single {
+            option: name(option),
+        }
+    }
+
+    fn tallies(poll: &Poll) -> Vec<u64> {
+        poll.results()
+            .tallies
+            .iter()
+            .map(|option| option.votes)
+            .collect()
+    }
+
+    #[test]
+    fn test_plurality_one_ballot_per_voter() {
+        let mut poll = new_poll(VotingMode::Plurality, PollSettings::default());
+
+        assert_eq!(poll.vote(owner(1), origin(), single("A"), 1), Ok(()));
+        assert_eq!(
+            poll.vote(owner(1), origin(), single("A"), 1),
+            Err(PollError::AlreadyVoted)
+        );
+
+        assert_eq!(tallies(&poll), vec![1, 0, 0]);
+        assert_eq!(poll.total_votes, 1);
+    }
+
+    #[test]
+    fn test_approval_voting() {
+        let mut poll = new_poll(VotingMode::Approval, PollSettings::default());
+
+        let ballot = Ballot::Approval {
+            options: vec![name("A"), OptionRef::Index(2)],
+        };
+        poll.vote(owner(1), origin(), ballot, 1).unwrap();
+        let ballot = Ballot::Approval {
+            options: vec![name("A")],
+        };
+        poll.vote(owner(2), origin(), ballot, 1).unwrap();
+
+        let ballot = Ballot::Approval {
+            options: vec![name("B"), OptionRef::Index(1)],
+        };
+        assert_eq!(
+            poll.vote(owner(3), origin(), ballot, 1),
+            Err(PollError::DuplicateOption("B".to_string()))
+        );
+        assert_eq!(
+            poll.vote(owner(3), origin(), single("B"), 1),
+            Err(PollError::BallotMismatch)
+        );
+
+        let results = poll.results();
+        assert_eq!(tallies(&poll), vec![2, 0, 1]);
+        assert_eq!(results.total_votes, 2);
+    }
+
+    #[test]
+    fn test_quadratic_voting_budget() {
+        let mode = VotingMode::Quadratic { credits: 10 };
+        let mut poll = new_poll(mode, PollSettings::default());
+
+        let ballot = Ballot::Quadratic {
+            votes: vec![(name("A"), 3), (name("B"), 1)],
+        };
+        poll.vote(owner(1), origin(), ballot, 1).unwrap();
+
+        let ballot = Ballot::Quadratic {
+            votes: vec![(name("A"), 4)],
+        };
+        assert_eq!(
+            poll.vote(owner(2), origin(), ballot, 1),
+            Err(PollError::InsufficientCredits {
+                cost: 16,
+                budget: 10
+            })
+        );
+
+        let results = poll.results();
+        assert_eq!(tallies(&poll), vec![3, 1, 0]);
+        assert_eq!(results.credits_spent, 10);
+    }
+
+    #[test]
+    fn test_token_weighted_voting() {
+        let mode = VotingMode::TokenWeighted {
+            token: ApplicationId::from([9; 32]),
+            weighting: Weighting::Snapshot {
+                holders: vec![owner(1), owner(2)],
+            },
+        };
+        let mut poll = new_poll(mode, PollSettings::default());
+
+        poll.vote(owner(1), origin(), single("A"), 70).unwrap();
+        poll.vote(owner(2), origin(), single("B"), 30).unwrap();
+        assert_eq!(
+            poll.vote(owner(3), origin(), single("B"), 0),
+            Err(PollError::NoVotingWeight)
+        );
+
+        let results = poll.results();
+        assert_eq!(tallies(&poll), vec![70, 30, 0]);
+        assert_eq!(results.total_votes, 2);
+        assert_eq!(results.total_weight, 100);
+
+        assert_eq!(
+            poll.vote(owner(3), origin(), single("A"), u64::MAX),
+            Err(PollError::WeightOverflow)
+        );
+    }
+
+    #[test]
+    fn test_secret_ballot_commit_reveal() {
+        let settings = PollSettings {
+            secret_ballot: true,
+            ..PollSettings::default()
+        };
+        let mut poll = new_poll(VotingMode::Plurality, settings);
+
+        assert_eq!(
+            poll.vote(owner(1), origin(), single("A"), 1),
+            Err(PollError::SecretBallot)
+        );
+        for (voter, option) in [(1, "A"), (2, "B"), (3, "C")] {
+            let hash = commitment_hash(&owner(voter), option, "salt");
+            poll.commit(owner(voter), hash).unwrap();
+        }
+        assert!(poll.has_voted(&owner(3)));
+        assert!(!poll.has_voted(&owner(4)));
+        assert_eq!(
+            poll.reveal(owner(1), origin(), name("A"), "salt", 1),
+            Err(PollError::PollStillActive)
+        );
+        assert_eq!(tallies(&poll), vec![0, 0, 0]);
+
+        assert_eq!(poll.close(Some(owner(0)), now()), Ok(Outcome::Tie));
+        poll.reveal(owner(1), origin(), name("A"), "salt", 1)
+            .unwrap();
+        assert_eq!(poll.outcome, Some(Outcome::Passed { option: 0 }));
+        assert_eq!(
+            poll.reveal(owner(2), origin(), name("A"), "salt", 1),
+            Err(PollError::CommitmentMismatch)
+        );
+        poll.reveal(owner(2), origin(), OptionRef::Index(1), "salt", 1)
+            .unwrap();
+
+        let results = poll.results();
+        assert_eq!(tallies(&poll), vec![1, 1, 0]);
+        assert_eq!(results.total_votes, 2);
+        assert_eq!(results.unrevealed, vec![owner(3)]);
+    }
+
+    #[test]
+    fn test_remote_votes_need_a_known_poll() {
+        let poll = Poll::default();
+        assert_eq!(poll.check_poll_id(0), Err(PollError::UnknownPoll(0)));
+
+        let mut poll = new_poll(VotingMode::Plurality, PollSettings::default());
+        assert_eq!(poll.check_poll_id(1), Ok(()));
+        assert_eq!(poll.check_poll_id(2), Err(PollError::UnknownPoll(2)));
+
+        // Anyone can replace the active poll, which is closed and kept in the history.
+        poll.vote(owner(1), origin(), single("B"), 1).unwrap();
+        poll.create(
+            Some(owner(1)),
+            "Q2".to_string(),
+            vec!["A".into(), "B".into()],
+            VotingMode::Plurality,
+            PollSettings::default(),
+            HashMap::new(),
+            now(),
+        )
+        .unwrap();
+        assert_eq!(poll.check_poll_id(1), Err(PollError::UnknownPoll(1)));
+        assert_eq!(poll.check_poll_id(2), Ok(()));
+        let replaced = poll.round_results(1, 1).unwrap();
+        assert_eq!(replaced.outcome, Some(Outcome::Passed { option: 1 }));
+    }
+
+    #[test]
+    fn test_option_validation() {
+        let mut poll = Poll::default();
+        let mut create = |options: &[&str]| {
+            let options = options
+                .iter()
+                .map(|option| NewOption::from(*option))
+                .collect();
+            let mode = VotingMode::Plurality;
+            poll.create(
+                None,
+                "Q".to_string(),
+                options,
+                mode,
+                PollSettings::default(),
+                HashMap::new(),
+                now(),
+            )
+        };
+
+        assert_eq!(create(&["A"]), Err(PollError::InvalidOptionCount(1)));
+        assert_eq!(create(&["A", " "]), Err(PollError::EmptyOptionName));
+        assert_eq!(
+            create(&["A", "B", "A"]),
+            Err(PollError::DuplicateOption("A".to_string()))
+        );
+        assert_eq!(create(&["C", "A", "B"]), Ok(()));
+
+        let names: Vec<_> = poll
+            .options
+            .iter()
+            .map(|option| option.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["C", "A", "B"]);
+        assert_eq!(poll.resolve(&name("B")), Ok(2));
+        assert_eq!(poll.resolve(&OptionRef::Index(0)), Ok(0));
+        assert_eq!(
+            poll.resolve(&OptionRef::Index(3)),
+            Err(PollError::UnknownOption(OptionRef::Index(3)))
+        );
+    }
+
+    #[test]
+    fn test_outcome_with_quorum_and_threshold() {
+        let settings = PollSettings {
+            quorum: Some(Quorum::Voters(3)),
+            pass_threshold: Some(Ratio {
+                numerator: 2,
+                denominator: 3,
+            }),
+            ..PollSettings::default()
+        };
+        let mut poll = new_poll(VotingMode::Plurality, settings);
+        poll.vote(owner(1), origin(), single("A"), 1).unwrap();
+        poll.vote(owner(2), origin(), single("A"), 1).unwrap();
+        assert_eq!(poll.tally(), Outcome::QuorumNotMet);
+
+        poll.vote(owner(3), origin(), single("B"), 1).unwrap();
+        assert_eq!(poll.tally(), Outcome::Passed { option: 0 });
+
+        poll.vote(owner(4), origin(), single("B"), 1).unwrap();
+        poll.vote(owner(5), origin(), single("A"), 1).unwrap();
+        assert_eq!(poll.close(Some(owner(0)), now()), Ok(Outcome::Rejected));
+        assert_eq!(poll.results().outcome, Some(Outcome::Rejected));
+        assert_eq!(
+            poll.reopen(Some(owner(0)), now()),
+            Err(PollError::PollFinalized)
+        );
+    }
+
+    #[test]
+    fn test_allow_list_eligibility() {
+        let chain = ChainId::from([7; 32]);
+        let settings = PollSettings {
+            eligibility: Eligibility::AllowList {
+                owners: vec![owner(1)],
+                chains: vec![chain],
+            },
+            ..PollSettings::default()
+        };
+        let mut poll = new_poll(VotingMode::Plurality, settings);
+        let elsewhere = ChainId::from([8; 32]);
+        assert!(poll.is_eligible(&owner(1), &elsewhere, None));
+        assert!(poll.is_eligible(&owner(2), &chain, None));
+        assert!(!poll.is_eligible(&owner(2), &elsewhere, None));
+
+        let voter = EligibleVoter::Owner(owner(2));
+        assert_eq!(
+            poll.add_eligible(Some(owner(1)), voter),
+            Err(PollError::NotCreator)
+        );
+        poll.add_eligible(Some(owner(0)), voter).unwrap();
+        assert!(poll.is_eligible(&owner(2), &elsewhere, None));
+        poll.remove_eligible(Some(owner(0)), EligibleVoter::Owner(owner(1)))
+            .unwrap();
+        assert!(!poll.is_eligible(&owner(1), &elsewhere, None));
+
+        let mut open = new_poll(VotingMode::Plurality, PollSettings::default());
+        assert_eq!(
+            open.add_eligible(Some(owner(0)), voter),
+            Err(PollError::NoAllowList)
+        );
+    }
+
+    #[test]
+    fn test_action_executes_once_after_passing() {
+        let action = ProposalAction {
+            application_id: ApplicationId::from([5; 32]),
+            call: vec![1, 2, 3],
+        };
+        let settings = PollSettings {
+            action: Some(action.clone()),
+            ..PollSettings::default()
+        };
+        let mut poll = new_poll(VotingMode::Plurality, settings);
+        poll.vote(owner(1), origin(), single("A"), 1).unwrap();
+        assert_eq!(poll.take_action(now()), Err(PollError::PollStillActive));
+
+        // Only the creator can close the poll, or replace it before its action ran.
+        assert_eq!(
+            poll.close(Some(owner(1)), now()),
+            Err(PollError::NotCreator)
+        );
+        poll.close(Some(owner(0)), now()).unwrap();
+        assert_eq!(
+            poll.close(Some(owner(0)), now()),
+            Err(PollError::PollClosed)
+        );
+        let options = vec!["A".into(), "B".into()];
+        assert_eq!(
+            poll.create(
+                Some(owner(1)),
+                "Q".to_string(),
+                options,
+                VotingMode::Plurality,
+                PollSettings::default(),
+                HashMap::new(),
+                now()
+            ),
+            Err(PollError::ActionPending)
+        );
+        assert_eq!(poll.take_action(now()), Ok(action.clone()));
+        assert_eq!(poll.take_action(now()), Err(PollError::AlreadyExecuted));
+
+        let settings = PollSettings {
+            action: poll.settings.action.clone(),
+            ..PollSettings::default()
+        };
+        let mut tied = new_poll(VotingMode::Plurality, settings.clone());
+        tied.close(Some(owner(0)), now()).unwrap();
+        assert_eq!(tied.take_action(now()), Err(PollError::NotPassed));
+
+        // The creator can cancel a pending action, which lets a new poll be created.
+        let mut cancelled = new_poll(VotingMode::Plurality, settings.clone());
+        cancelled.vote(owner(1), origin(), single("A"), 1).unwrap();
+        cancelled.close(Some(owner(0)), now()).unwrap();
+        assert_eq!(
+            cancelled.cancel_action(Some(owner(1))),
+            Err(PollError::NotCreator)
+        );
+        cancelled.cancel_action(Some(owner(0))).unwrap();
+        assert_eq!(
+            cancelled.take_action(now()),
+            Err(PollError::ActionCancelled)
+        );
+        assert!(cancelled.history[0].action_cancelled);
+        cancelled
+            .create(
+                Some(owner(1)),
+                "Q".to_string(),
+                vec!["A".into(), "B".into()],
+                VotingMode::Plurality,
+                PollSettings::default(),
+                HashMap::new(),
+                now(),
+            )
+            .unwrap();
+
+        // A secret poll runs its action once every ballot is revealed, or once the revealing
+        // period is over, after which unrevealed ballots count as abstentions.
+        let settings = PollSettings {
+            secret_ballot: true,
+            reveal_period_micros: Some(1_000),
+            ..settings
+        };
+        let mut secret = new_poll(VotingMode::Plurality, settings);
+        for (voter, option) in [(1, "A"), (2, "B"), (3, "A")] {
+            let hash = commitment_hash(&owner(voter), option, "salt");
+            secret.commit(owner(voter), hash).unwrap();
+        }
+        secret.close(Some(owner(0)), now()).unwrap();
+        for voter in [1, 3] {
+            secret
+                .reveal(owner(voter), origin(), name("A"), "salt", 1)
+                .unwrap();
+        }
+        assert_eq!(secret.take_action(now()), Err(PollError::UnrevealedBallots));
+        let deadline = Timestamp::from(1_000);
+        let late = VoteOrigin {
+            timestamp: deadline,
+            ..origin()
+        };
+        assert_eq!(
+            secret.reveal(owner(2), late, name("B"), "salt", 1),
+            Err(PollError::RevealClosed)
+        );
+        assert_eq!(secret.take_action(deadline), Ok(action));
+    }
+
+    #[test]
+    fn test_vote_log_audit() {
+        let mut poll = new_poll(VotingMode::Approval, PollSettings::default());
+        let ballot = Ballot::Approval {
+            options: vec![name("A"), name("C")],
+        };
+        poll.vote(owner(1), origin(), ballot, 1).unwrap();
+        poll.vote(owner(2), origin(), single("C"), 1).unwrap_err();
+        let ballot = Ballot::Approval {
+            options: vec![name("C")],
+        };
+        poll.vote(owner(2), origin(), ballot, 1).unwrap();
+
+        assert_eq!(poll.log.len(), 2);
+        assert_eq!(poll.log[0].voter, owner(1));
+        assert_eq!(poll.log[0].votes, vec![(0, 1), (2, 1)]);
+        let audit = poll.audit().unwrap();
+        assert_eq!(audit.tallies, vec![1, 0, 2]);
+        assert!(audit.matches);
+
+        poll.options[1].votes += 1;
+        assert!(!poll.audit().unwrap().matches);
+    }
+
+    #[test]
+    fn test_transitive_delegation() {
+        let mut poll = new_poll(VotingMode::Plurality, PollSettings::default());
+        poll.delegate(owner(1), owner(2), 1).unwrap();
+        poll.delegate(owner(2), owner(3), 1).unwrap();
+        assert_eq!(
+            poll.delegate(owner(3), owner(1), 1),
+            Err(PollError::DelegationCycle)
+        );
+        assert_eq!(
+            poll.delegate(owner(4), owner(4), 1),
+            Err(PollError::DelegationCycle)
+        );
+
+        poll.vote(owner(3), origin(), single("A"), 1).unwrap();
+        assert_eq!(tallies(&poll), vec![3, 0, 0]);
+        assert_eq!(poll.total_weight, 3);
+
+        // A direct vote overrides the delegation, also for the voters delegating through it.
+        poll.vote(owner(2), origin(), single("B"), 1).unwrap();
+        assert_eq!(tallies(&poll), vec![1, 2, 0]);
+        assert_eq!(
+            poll.delegate(owner(2), owner(3), 1),
+            Err(PollError::AlreadyVoted)
+        );
+        assert!(poll.audit().unwrap().matches);
+
+        // Delegating after the delegate voted, or changing one's delegate, moves the weight.
+        poll.delegate(owner(5), owner(4), 2).unwrap();
+        poll.delegate(owner(4), owner(3), 1).unwrap();
+        assert_eq!(tallies(&poll), vec![4, 2, 0]);
+        poll.delegate(owner(4), owner(2), 1).unwrap();
+        assert_eq!(tallies(&poll), vec![1, 5, 0]);
+        assert_eq!(poll.total_weight, 6);
+        assert!(poll.audit().unwrap().matches);
+    }
+
+    #[test]
+    fn test_templates_and_metadata() {
+        let (options, mode) = PollTemplate::Rating.into_parts();
+        let names: Vec<_> = options.iter().map(|option| option.name.as_str()).collect();
+        assert_eq!(names, vec!["1", "2", "3", "4", "5"]);
+        assert_eq!(mode, VotingMode::Plurality);
+
+        let option = NewOption {
+            name: "Red".to_string(),
+            metadata: OptionMetadata {
+                description: Some("The colour of fire".to_string()),
+                image_url: Some("https://example.com/red.png".to_string()),
+                link: None,
+            },
+        };
+        let template = PollTemplate::MultipleChoice {
+            options: vec![option.clone(), "Blue".into()],
+        };
+        let (options, mode) = template.into_parts();
+        assert_eq!(mode, VotingMode::Approval);
+
+        let settings = PollSettings {
+            metadata: PollMetadata {
+                description: Some("Pick your colours".to_string()),
+                tags: vec!["colours".to_string()],
+                category: Some("Design".to_string()),
+            },
+            ..PollSettings::default()
+        };
+        let mut poll = Poll::default();
+        poll.create(
+            None,
+            "Q".to_string(),
+            options,
+            mode,
+            settings,
+            HashMap::new(),
+            now(),
+        )
+        .unwrap();
+        assert_eq!(poll.options[0].metadata, option.metadata);
+        assert_eq!(poll.options[1].metadata, OptionMetadata::default());
+        assert_eq!(poll.settings.metadata.tags, vec!["colours".to_string()]);
+    }
+
+    #[test]
+    fn test_survey_responses_and_cross_tab() {
+        let mut survey = Survey::default();
+        let question = |question: &str, options: &[&str], mode| NewQuestion {
+            question: question.to_string(),
+            options: options
+                .iter()
+                .map(|option| NewOption::from(*option))
+                .collect(),
+            mode,
+        };
+        let questions = vec![
+            question("Role?", &["Dev", "Ops"], VotingMode::Plurality),
+            question("Tools?", &["Rust", "Go", "Nix"], VotingMode::Approval),
+        ];
+        survey
+            .create(
+                Some(owner(0)),
+                "Team".to_string(),
+                questions.clone(),
+                SurveySettings::default(),
+            )
+            .unwrap();
+        assert_eq!(
+            survey.create(
+                Some(owner(1)),
+                "Other".to_string(),
+                questions,
+                SurveySettings::default()
+            ),
+            Err(PollError::NotCreator)
+        );
+
+        let approve = |options: &[&str]| Ballot::Approval {
+            options: options.iter().map(|option| name(option)).collect(),
+        };
+        survey
+            .respond(owner(1), vec![single("Dev"), approve(&["Rust", "Nix"])])
+            .unwrap();
+        survey
+            .respond(owner(2), vec![single("Ops"), approve(&["Go", "Nix"])])
+            .unwrap();
+        survey
+            .respond(owner(3), vec![single("Dev"), approve(&["Rust"])])
+            .unwrap();
+
+        // Responses are atomic: an invalid answer rejects the whole response.
+        assert_eq!(
+            survey.respond(owner(4), vec![single("Ops"), single("Go")]),
+            Err(PollError::BallotMismatch)
+        );
+        assert_eq!(
+            survey.respond(owner(4), vec![single("Ops")]),
+            Err(PollError::AnswerCountMismatch {
+                expected: 2,
+                actual: 1
+            })
+        );
+        let counts: Vec<_> = survey.questions[0]
+            .options
+            .iter()
+            .map(|option| option.votes)
+            .collect();
+        assert_eq!(counts, vec![2, 1]);
+
+        assert_eq!(survey.cross_tab(0, 0, 1), Ok(vec![2, 0, 1]));
+        assert_eq!(survey.cross_tab(1, 2, 0), Ok(vec![1, 1]));
+        assert_eq!(
+            survey.cross_tab(0, 0, 2),
+            Err(PollError::UnknownQuestion(2))
+        );
+
+        assert_eq!(survey.close(Some(owner(1))), Err(PollError::NotCreator));
+        survey.close(Some(owner(0))).unwrap();
+        assert_eq!(
+            survey.respond(owner(4), vec![single("Ops"), approve(&["Go"])]),
+            Err(PollError::SurveyClosed)
+        );
+    }
+
+    #[test]
+    fn test_rounds_keep_history() {
+        let settings = PollSettings {
+            reopen: ReopenPolicy::NewRound,
+            ..PollSettings::default()
+        };
+        let mut poll = new_poll(VotingMode::Plurality, settings);
+        poll.vote(owner(1), origin(), single("A"), 1).unwrap();
+        assert_eq!(
+            poll.reopen(Some(owner(0)), now()),
+            Err(PollError::PollStillActive)
+        );
+        assert_eq!(
+            poll.close(Some(owner(0)), now()),
+            Ok(Outcome::Passed { option: 0 })
+        );
+
+        assert_eq!(
+            poll.reopen(Some(owner(1)), now()),
+            Err(PollError::NotCreator)
+        );
+        poll.reopen(Some(owner(0)), now()).unwrap();
+        assert_eq!(poll.round, 2);
+        assert_eq!(tallies(&poll), vec![0, 0, 0]);
+        poll.vote(owner(1), origin(), single("B"), 1).unwrap();
+        poll.close(Some(owner(0)), now()).unwrap();
+
+        poll.create(
+            None,
+            "Q2".to_string(),
+            vec!["A".into(), "B".into()],
+            VotingMode::Plurality,
+            PollSettings::default(),
+            HashMap::new(),
+            now(),
+        )
+        .unwrap();
+        assert_eq!(poll.round, 1);
+        poll.close(None, now()).unwrap();
+        assert_eq!(
+            poll.reopen(Some(owner(0)), now()),
+            Err(PollError::PollFinalized)
+        );
+
+        let rounds: Vec<_> = poll
+            .history
+            .iter()
+            .map(|results| (results.poll_id, results.round, results.outcome.clone()))
+            .collect();
+        assert_eq!(
+            rounds,
+            vec![
+                (1, 1, Some(Outcome::Passed { option: 0 })),
+                (1, 2, Some(Outcome::Passed { option: 1 })),
+                (2, 1, Some(Outcome::Tie)),
+            ]
+        );
+        let first = poll.round_results(1, 1).unwrap();
+        assert_eq!(first.tallies[0].votes, 1);
+        assert_eq!(first.question, "Q");
+
+        // A passed poll cannot be reopened before its action is executed.
+        let settings = PollSettings {
+            reopen: ReopenPolicy::NewRound,
+            action: Some(ProposalAction {
+                application_id: ApplicationId::from([5; 32]),
+                call: vec![],
+            }),
+            ..PollSettings::default()
+        };
+        let mut poll = new_poll(VotingMode::Plurality, settings);
+        poll.vote(owner(1), origin(), single("A"), 1).unwrap();
+        poll.close(Some(owner(0)), now()).unwrap();
+        assert_eq!(
+            poll.reopen(Some(owner(0)), now()),
+            Err(PollError::ActionPending)
+        );
+        poll.take_action(now()).unwrap();
+        assert_eq!(
+            poll.reopen(Some(owner(0)), now()),
+            Err(PollError::PollFinalized)
+        );
+    }
+
+    #[test]
+    fn test_anti_spam_limits() {
+        let limits = PollLimits {
+            rate_limit: Some(RateLimit {
+                votes: 2,
+                window_micros: 1_000,
+            }),
+            max_options: Some(3),
+            max_voters: Some(3),
+            ..PollLimits::default()
+        };
+        let settings = PollSettings {
+            limits: limits.clone(),
+            ..PollSettings::default()
+        };
+        let mut poll = new_poll(VotingMode::Plurality, settings.clone());
+
+        let chain = ChainId::from([2; 32]);
+        let at = |micros: u64| Timestamp::from(micros);
+        assert_eq!(poll.check_rate_limit(chain, at(0)), Ok(()));
+        assert_eq!(poll.check_rate_limit(chain, at(500)), Ok(()));
+        assert_eq!(
+            poll.check_rate_limit(chain, at(999)),
+            Err(PollError::RateLimited)
+        );
+        assert_eq!(poll.check_rate_limit(origin().chain_id, at(999)), Ok(()));
+        assert_eq!(poll.check_rate_limit(chain, at(1_000)), Ok(()));
+
+        for voter in 1..=3 {
+            poll.vote(owner(voter), origin(), single("A"), 1).unwrap();
+        }
+        assert_eq!(
+            poll.vote(owner(4), origin(), single("A"), 1),
+            Err(PollError::TooManyVoters)
+        );
+
+        // Survey responses have their own limits, not the poll's.
+        let question = NewQuestion {
+            question: "Q".to_string(),
+            options: vec!["A".into(), "B".into()],
+            mode: VotingMode::Plurality,
+        };
+        let survey_settings = SurveySettings {
+            limits: PollLimits {
+                max_voters: Some(4),
+                vote_fee: Some(VoteFee {
+                    token: ApplicationId::from([5; 32]),
+                    amount: 10,
+                }),
+                ..PollLimits::default()
+            },
+            ..SurveySettings::default()
+        };
+        assert_eq!(
+            poll.survey.create(
+                None,
+                "S".to_string(),
+                vec![question.clone()],
+                survey_settings.clone()
+            ),
+            Err(PollError::NoFeeRecipient)
+        );
+        poll.survey
+            .create(
+                Some(owner(0)),
+                "S".to_string(),
+                vec![question],
+                survey_settings,
+            )
+            .unwrap();
+        for respondent in 1..=4 {
+            poll.survey
+                .respond(owner(respondent), vec![single("B")])
+                .unwrap();
+        }
+        assert_eq!(
+            poll.survey.respond(owner(5), vec![single("B")]),
+            Err(PollError::TooManyVoters)
+        );
+        assert_eq!(poll.survey.check_rate_limit(chain, at(999)), Ok(()));
+
+        let options = vec!["A".into(), "B".into(), "C".into(), "D".into()];
+        let mode = VotingMode::Plurality;
+        assert_eq!(
+            poll.create(
+                None,
+                "Q".to_string(),
+                options,
+                mode,
+                settings,
+                HashMap::new(),
+                now()
+            ),
+            Err(PollError::InvalidOptionCount(4))
+        );
+
+        let settings = PollSettings {
+            limits: PollLimits {
+                vote_fee: Some(VoteFee {
+                    token: ApplicationId::from([5; 32]),
+                    amount: 10,
+                }),
+                ..limits
+            },
+            ..PollSettings::default()
+        };
+        let options = vec!["A".into(), "B".into()];
+        let mode = VotingMode::Plurality;
+        assert_eq!(
+            poll.create(
+                None,
+                "Q".to_string(),
+                options,
+                mode,
+                settings,
+                HashMap::new(),
+                now()
+            ),
+            Err(PollError::NoFeeRecipient)
+        );
+    }
 }
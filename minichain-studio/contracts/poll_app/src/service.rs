@@ -0,0 +1,355 @@
+use crate::{
+    Ballot, NewOption, NewQuestion, OptionRef, Outcome, Poll, PollLimits, PollOperation,
+    PollResults, PollSettings, PollTemplate, ProposalAction, Survey, SurveySettings, TallyAudit,
+    VoteLogEntry, VotingMode,
+};
+use async_graphql::{
+    EmptySubscription, Enum, Json, Object, Request, Response, Schema, SimpleObject,
+};
+use async_trait::async_trait;
+use linera_sdk::{
+    base::{ChainId, Owner},
+    QueryContext, Service, ViewStateStorage,
+};
+use std::sync::Arc;
+
+#[async_trait]
+impl Service for Poll {
+    type Error = linera_sdk::base::Error;
+    type Storage = ViewStateStorage<Self>;
+
+    async fn handle_query(
+        self: Arc<Self>,
+        _context: &QueryContext,
+        request: Request,
+    ) -> Result<Response, Self::Error> {
+        let schema = Schema::build(QueryRoot(self), MutationRoot, EmptySubscription).finish();
+        Ok(schema.execute(request).await)
+    }
+}
+
+/// Whether a poll still accepts ballots.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Enum)]
+pub enum PollStatus {
+    /// Ballots or commitments are accepted.
+    Active,
+    /// The poll is closed. Secret ballots can be revealed.
+    Closed,
+}
+
+/// An option of the poll with its current count.
+#[derive(Debug, SimpleObject)]
+pub struct OptionResult {
+    /// The position of the option.
+    pub index: usize,
+    /// The name of the option.
+    pub name: String,
+    /// The votes, approvals or weight the option received.
+    pub votes: u64,
+    /// The share of all counted votes that went to this option, in percent.
+    pub percentage: f64,
+    /// A longer description of the option.
+    pub description: Option<String>,
+    /// The URL of an image illustrating the option.
+    pub image_url: Option<String>,
+    /// A link to more information about the option.
+    pub link: Option<String>,
+}
+
+/// The queries on the poll hosted on this chain.
+struct QueryRoot(Arc<Poll>);
+
+#[Object]
+impl QueryRoot {
+    /// Identifies the current poll, see [`Poll::id`].
+    async fn id(&self) -> u64 {
+        self.0.id
+    }
+
+    async fn question(&self) -> &str {
+        &self.0.question
+    }
+
+    async fn description(&self) -> Option<&str> {
+        self.0.settings.metadata.description.as_deref()
+    }
+
+    async fn tags(&self) -> &[String] {
+        &self.0.settings.metadata.tags
+    }
+
+    async fn category(&self) -> Option<&str> {
+        self.0.settings.metadata.category.as_deref()
+    }
+
+    async fn status(&self) -> PollStatus {
+        if self.0.is_active {
+            PollStatus::Active
+        } else {
+            PollStatus::Closed
+        }
+    }
+
+    async fn mode(&self) -> Json<VotingMode> {
+        Json(self.0.mode.clone())
+    }
+
+    /// The anti-spam limits of the poll.
+    async fn limits(&self) -> Json<PollLimits> {
+        Json(self.0.settings.limits.clone())
+    }
+
+    /// The options in creation order, with their counts and percentages.
+    async fn options(&self) -> Vec<OptionResult> {
+        let counted: u64 = self.0.options.iter().map(|option| option.votes).sum();
+        self.0
+            .options
+            .iter()
+            .enumerate()
+            .map(|(index, option)| OptionResult {
+                index,
+                name: option.name.clone(),
+                votes: option.votes,
+                percentage: if counted == 0 {
+                    0.0
+                } else {
+                    option.votes as f64 * 100.0 / counted as f64
+                },
+                description: option.metadata.description.clone(),
+                image_url: option.metadata.image_url.clone(),
+                link: option.metadata.link.clone(),
+            })
+            .collect()
+    }
+
+    async fn total_votes(&self) -> u64 {
+        self.0.total_votes
+    }
+
+    async fn total_weight(&self) -> u64 {
+        self.0.total_weight
+    }
+
+    /// The number of participants, as last returned by the poll's participant counter.
+    async fn participants(&self) -> Option<u64> {
+        self.0.participants
+    }
+
+    /// The round of the current poll.
+    async fn round(&self) -> u64 {
+        self.0.round
+    }
+
+    /// The results recorded when each round closed, of every poll created on this chain.
+    async fn history(&self) -> Json<Vec<PollResults>> {
+        Json(self.0.history.clone())
+    }
+
+    /// The results recorded when round `round` of poll `poll_id` closed.
+    async fn round_results(&self, poll_id: u64, round: u64) -> Option<Json<PollResults>> {
+        self.0.round_results(poll_id, round).cloned().map(Json)
+    }
+
+    /// The outcome of the poll, once it is closed.
+    async fn outcome(&self) -> Option<Json<Outcome>> {
+        self.0.outcome.clone().map(Json)
+    }
+
+    /// The action executed if the poll passes.
+    async fn action(&self) -> Option<Json<ProposalAction>> {
+        self.0.settings.action.clone().map(Json)
+    }
+
+    /// The value returned by the poll's action, once it was executed.
+    async fn action_result(&self) -> Option<Vec<u8>> {
+        self.0.action_result.clone()
+    }
+
+    /// Whether the creator cancelled the poll's action instead of executing it.
+    async fn action_cancelled(&self) -> bool {
+        self.0.action_cancelled
+    }
+
+    /// When the revealing period of the closed secret poll ends, in microseconds.
+    async fn reveal_deadline(&self) -> Option<u64> {
+        self.0.reveal_deadline.map(|deadline| deadline.micros())
+    }
+
+    /// Whether `owner` has cast or committed a ballot.
+    async fn has_voted(&self, owner: Owner) -> bool {
+        self.0.has_voted(&owner)
+    }
+
+    /// The voters of a secret poll who have not revealed their ballot.
+    async fn unrevealed(&self) -> Vec<Owner> {
+        self.0.unrevealed()
+    }
+
+    /// The owner `owner` delegated their vote to, if any.
+    async fn delegation(&self, owner: Owner) -> Option<Owner> {
+        self.0
+            .delegations
+            .get(&owner)
+            .map(|delegation| delegation.to)
+    }
+
+    /// Every counted ballot, in the order it was counted.
+    async fn vote_log(&self) -> Json<Vec<VoteLogEntry>> {
+        Json(self.0.log.clone())
+    }
+
+    /// The vote log exported as a JSON document.
+    async fn vote_log_json(&self) -> String {
+        serde_json::to_string(&self.0.log).expect("vote log entries are serializable")
+    }
+
+    /// The vote log exported as CSV, with one row per option a ballot voted for.
+    async fn vote_log_csv(&self) -> String {
+        let mut csv = String::from("entry,voter,chain_id,timestamp,option,name,votes,weight\n");
+        for (entry_index, entry) in self.0.log.iter().enumerate() {
+            for (option, votes) in &entry.votes {
+                let name = self
+                    .0
+                    .options
+                    .get(*option)
+                    .map_or("", |option| option.name.as_str());
+                csv.push_str(&format!(
+                    "{},{},{},{},{},\"{}\",{},{}\n",
+                    entry_index,
+                    entry.voter,
+                    entry.origin.chain_id,
+                    entry.origin.timestamp.micros(),
+                    option,
+                    name.replace('"', "\"\""),
+                    votes,
+                    entry.weight,
+                ));
+            }
+        }
+        csv
+    }
+
+    /// The survey hosted on this chain, with the counts of each question.
+    async fn survey(&self) -> Json<Survey> {
+        Json(self.0.survey.clone())
+    }
+
+    /// The counts of the options of survey question `other` among the respondents who chose
+    /// option `option` of question `question`.
+    async fn cross_tab(
+        &self,
+        question: usize,
+        option: usize,
+        other: usize,
+    ) -> async_graphql::Result<Vec<u64>> {
+        Ok(self.0.survey.cross_tab(question, option, other)?)
+    }
+
+    /// Recomputes the tallies from the vote log and checks them against the stored counts.
+    async fn audit(&self) -> async_graphql::Result<Json<TallyAudit>> {
+        Ok(Json(self.0.audit()?))
+    }
+}
+
+/// The mutations, each returning a serialized [`PollOperation`] to be submitted.
+struct MutationRoot;
+
+#[Object]
+impl MutationRoot {
+    async fn create_poll(
+        &self,
+        question: String,
+        options: Json<Vec<NewOption>>,
+        mode: Json<VotingMode>,
+        settings: Option<Json<PollSettings>>,
+    ) -> Vec<u8> {
+        operation_bytes(PollOperation::CreatePoll {
+            question,
+            options: options.0,
+            mode: mode.0,
+            settings: settings.map(|settings| settings.0).unwrap_or_default(),
+        })
+    }
+
+    async fn create_from_template(
+        &self,
+        question: String,
+        template: Json<PollTemplate>,
+        settings: Option<Json<PollSettings>>,
+    ) -> Vec<u8> {
+        operation_bytes(PollOperation::CreateFromTemplate {
+            question,
+            template: template.0,
+            settings: settings.map(|settings| settings.0).unwrap_or_default(),
+        })
+    }
+
+    async fn vote(&self, ballot: Json<Ballot>) -> Vec<u8> {
+        operation_bytes(PollOperation::Vote { ballot: ballot.0 })
+    }
+
+    async fn vote_remote(
+        &self,
+        home_chain: ChainId,
+        poll_id: u64,
+        ballot: Json<Ballot>,
+    ) -> Vec<u8> {
+        operation_bytes(PollOperation::VoteRemote {
+            home_chain,
+            poll_id,
+            ballot: ballot.0,
+        })
+    }
+
+    async fn commit(&self, hash: Json<[u8; 32]>) -> Vec<u8> {
+        operation_bytes(PollOperation::Commit { hash: hash.0 })
+    }
+
+    async fn reveal(&self, option: Json<OptionRef>, salt: String) -> Vec<u8> {
+        operation_bytes(PollOperation::Reveal {
+            option: option.0,
+            salt,
+        })
+    }
+
+    async fn delegate(&self, to: Owner) -> Vec<u8> {
+        operation_bytes(PollOperation::Delegate { to })
+    }
+
+    async fn create_survey(
+        &self,
+        title: String,
+        questions: Json<Vec<NewQuestion>>,
+        settings: Option<Json<SurveySettings>>,
+    ) -> Vec<u8> {
+        operation_bytes(PollOperation::CreateSurvey {
+            title,
+            questions: questions.0,
+            settings: settings.map(|settings| settings.0).unwrap_or_default(),
+        })
+    }
+
+    async fn respond(&self, answers: Json<Vec<Ballot>>) -> Vec<u8> {
+        operation_bytes(PollOperation::Respond { answers: answers.0 })
+    }
+
+    async fn close_survey(&self) -> Vec<u8> {
+        operation_bytes(PollOperation::CloseSurvey)
+    }
+
+    async fn execute_action(&self) -> Vec<u8> {
+        operation_bytes(PollOperation::ExecuteAction)
+    }
+
+    async fn cancel_action(&self) -> Vec<u8> {
+        operation_bytes(PollOperation::CancelAction)
+    }
+}
+
+fn operation_bytes(operation: PollOperation) -> Vec<u8> {
+    bcs::to_bytes(&operation).expect("poll operations are serializable")
+}
+
+impl linera_sdk::service::WitInterface for Poll {
+    const EXPORTS: &'static [&'static str] = &["handle_query"];
+}
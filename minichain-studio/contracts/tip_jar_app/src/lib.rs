@@ -1,3 +1,4 @@
+use counter_abi::CounterApplicationCall;
 use linera_sdk::{
     base::{ApplicationId, ChainId, SessionId},
     contract::system_api,
@@ -18,6 +19,65 @@ pub struct TipJar {
     pub transactions: Vec<Transaction>,
     /// The owner of this tip jar.
     pub owner: String,
+    /// The counter application that counts the tips received, if any.
+    pub tip_counter: Option<ApplicationId>,
+    /// The number of tips received, as last returned by the tip counter.
+    pub tips_received: Option<u64>,
+}
+
+/// A session opened by a caller application, holding part of the balance reserved for tips.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TipJarSession {
+    /// The reserved amount not yet tipped. Returned to the balance when the session is closed.
+    pub remaining: u64,
+}
+
+impl TipJar {
+    /// Opens a session that can tip up to `amount`, reserving it from the balance.
+    pub fn open_session(&mut self, amount: u64) -> Option<TipJarSession> {
+        if self.balance < amount {
+            return None;
+        }
+        self.balance -= amount;
+        Some(TipJarSession { remaining: amount })
+    }
+
+    /// Returns the unspent reservation of `session` to the balance.
+    pub fn close_session(&mut self, session: &TipJarSession) {
+        self.balance += session.remaining;
+    }
+}
+
+impl TipJar {
+    /// Counts a received tip with the tip counter, if any, and records the count it returns.
+    /// The count is best-effort: if the counter fails, the last count is kept and the tip is
+    /// still received, so that a failing counter cannot block incoming tips.
+    async fn count_tip(&mut self) {
+        let Some(counter) = self.tip_counter else {
+            return;
+        };
+        let call = CounterApplicationCall::IncrementKey {
+            key: "tips-received".to_string(),
+        };
+        if let Ok(result) =
+            system_api::call_application::<_, u64>(true, counter, &call, vec![]).await
+        {
+            if let Some(tips_received) = result.value {
+                self.tips_received = Some(tips_received);
+            }
+        }
+    }
+}
+
+impl TipJarSession {
+    /// Spends `amount` of the reservation, if enough of it remains.
+    pub fn spend(&mut self, amount: u64) -> bool {
+        if self.remaining < amount {
+            return false;
+        }
+        self.remaining -= amount;
+        true
+    }
 }
 
 /// A transaction record.
@@ -63,6 +123,11 @@ pub enum TipJarOperation {
     ConnectChain { chain_id: ChainId },
     /// Disconnects from a chain.
     DisconnectChain { chain_id: ChainId },
+    /// Sets the counter application that counts the tips received. The tip jar needs a caller
+    /// grant with write permission on it.
+    SetTipCounter {
+        application_id: Option<ApplicationId>,
+    },
 }
 
 /// The message types that can be sent to the tip jar application.
@@ -91,6 +156,8 @@ pub enum TipJarApplicationCall {
     GetConnections,
     /// Connects to another chain.
     ConnectChain { chain_id: ChainId },
+    /// Opens a session that can tip up to `amount`, reserving it from the balance.
+    OpenSession { amount: u64 },
 }
 
 /// The session call types that can be made to the tip jar application.
@@ -100,6 +167,10 @@ pub enum TipJarSessionCall {
     GetBalance,
     /// Gets the transaction history.
     GetTransactions,
+    /// Sends a tip to another chain out of the session's reservation.
+    SendTip { to_chain: ChainId, amount: u64 },
+    /// Returns the unspent reservation to the balance and closes the session.
+    Close,
 }
 
 /// The effect types that can be sent by the tip jar application.
@@ -117,7 +188,7 @@ impl Contract for TipJar {
     type ApplicationCall = TipJarApplicationCall;
     type SessionCall = TipJarSessionCall;
     type Effect = TipJarEffect;
-    type SessionState = ();
+    type SessionState = TipJarSession;
     type ApplicationState = TipJar;
 
     async fn initialize(
@@ -200,6 +271,11 @@ impl Contract for TipJar {
                 self.connections.retain(|&id| id != chain_id);
                 Ok(ExecutionResult::default())
             }
+            TipJarOperation::SetTipCounter { application_id } => {
+                self.tip_counter = application_id;
+                self.tips_received = None;
+                Ok(ExecutionResult::default())
+            }
         }
     }
 
@@ -221,6 +297,7 @@ impl Contract for TipJar {
                     timestamp: system_api::current_system_time().as_millis(),
                 };
                 self.transactions.push(transaction);
+                self.count_tip().await;
                 Ok(ExecutionResult::default())
             }
             TipJarMessage::TipSent { to_chain, amount, transaction_id } => {
@@ -283,16 +360,70 @@ impl Contract for TipJar {
                 }
                 Ok(ApplicationCallResult::default())
             }
+            TipJarApplicationCall::OpenSession { amount } => {
+                let session = self
+                    .open_session(amount)
+                    .ok_or_else(linera_sdk::base::Error::default)?;
+                Ok(ApplicationCallResult {
+                    create_sessions: vec![session],
+                    ..Default::default()
+                })
+            }
         }
     }
 
     async fn handle_session_call(
         &mut self,
         _context: &CalleeContext,
-        _call: Self::SessionCall,
+        session: &mut Self::SessionState,
+        call: Self::SessionCall,
         _storage: ViewStateStorage<Self>,
     ) -> Result<SessionCallResult<Self::Effect>, linera_sdk::base::Error> {
-        Err(linera_sdk::base::Error::default())
+        match call {
+            TipJarSessionCall::GetBalance => Ok(SessionCallResult {
+                inner: ApplicationCallResult {
+                    value: Some(self.balance),
+                    effects: vec![],
+                },
+                close_session: false,
+            }),
+            TipJarSessionCall::GetTransactions => Ok(SessionCallResult {
+                inner: ApplicationCallResult {
+                    value: Some(self.transactions.clone()),
+                    effects: vec![],
+                },
+                close_session: false,
+            }),
+            TipJarSessionCall::SendTip { to_chain, amount } => {
+                if !session.spend(amount) {
+                    return Err(linera_sdk::base::Error::default());
+                }
+                let transaction = Transaction {
+                    id: format!("tip_{}", self.transactions.len()),
+                    amount,
+                    from_chain: _context.chain_id,
+                    to_chain,
+                    transaction_type: TransactionType::TipSent,
+                    timestamp: system_api::current_system_time().as_millis(),
+                };
+                self.transactions.push(transaction);
+                let effects = vec![TipJarEffect::SendTip { to_chain, amount }];
+                Ok(SessionCallResult {
+                    inner: ApplicationCallResult {
+                        effects,
+                        ..Default::default()
+                    },
+                    close_session: false,
+                })
+            }
+            TipJarSessionCall::Close => {
+                self.close_session(session);
+                Ok(SessionCallResult {
+                    close_session: true,
+                    ..Default::default()
+                })
+            }
+        }
     }
 
     async fn handle_effect(
@@ -328,6 +459,10 @@ pub struct TipJarView {
     pub transactions: Vec<Transaction>,
     /// The owner of this tip jar.
     pub owner: String,
+    /// The counter application that counts the tips received, if any.
+    pub tip_counter: Option<ApplicationId>,
+    /// The number of tips received, as last returned by the tip counter.
+    pub tips_received: Option<u64>,
 }
 
 impl linera_sdk::contract::WitInterface for TipJar {
@@ -382,4 +517,20 @@ mod tests {
         assert_eq!(tip_jar.connections.len(), 1);
         assert!(tip_jar.connections.contains(&chain_id));
     }
+
+    #[test]
+    fn test_tip_jar_session_reservation() {
+        let mut tip_jar = TipJar {
+            balance: 100,
+            ..TipJar::default()
+        };
+        assert_eq!(tip_jar.open_session(150), None);
+        let mut session = tip_jar.open_session(60).unwrap();
+        assert_eq!(tip_jar.balance, 40);
+
+        assert!(session.spend(25));
+        assert!(!session.spend(50));
+        tip_jar.close_session(&session);
+        assert_eq!(tip_jar.balance, 75);
+    }
 }